@@ -0,0 +1,389 @@
+use renderer::{
+    filter::{Dither, Filter, FilterChain, Levels},
+    hr_bw_display::Res,
+    scale::Resample,
+    transform::{Pipeline, Rotation, Transform},
+    types::{Rect, Size},
+};
+
+use std::str::FromStr;
+
+use crate::{FPS, HEIGHT, WIDTH};
+
+/// largest neighbourhood radius of the filters, their cost grows with it
+const MAX_RADIUS: usize = 64;
+
+pub struct Options {
+    /// raw `monob`, `.bapl`, Y4M or Netpbm clip to play, a `*` in the file name
+    /// plays numbered images, the embedded one if missing
+    pub input: Option<String>,
+    /// dimensions of a raw clip, containers bring their own
+    pub size: Size,
+    /// the source is shrunk by this factor, fractions below 1 enlarge it
+    pub scale: f32,
+    /// terminal cells to fit the picture into, overrides `scale`
+    pub fit: Option<Size>,
+    pub filter: Resample,
+    /// applied to every frame at source resolution, before scaling
+    pub pipeline: Pipeline,
+    /// applied to every frame after scaling
+    pub filters: FilterChain,
+    /// the clip's own rate or `FPS` if missing
+    pub fps: Option<u64>,
+    pub res: Option<Res>,
+    /// profiler overlay on the status line
+    pub profile: bool,
+    /// per frame timings are written here on exit
+    pub csv: Option<String>,
+    /// threads preparing frames, `pool::default_workers` if missing
+    pub workers: Option<usize>,
+    /// prepared frames kept ready for the player, a second's worth if missing
+    pub buffer: Option<usize>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            input: None,
+            size: Size::new(WIDTH, HEIGHT),
+            scale: 1.0,
+            fit: None,
+            filter: Resample::Box,
+            pipeline: Pipeline::new(),
+            filters: FilterChain::new(),
+            fps: None,
+            res: None,
+            profile: false,
+            csv: None,
+            workers: None,
+            buffer: None,
+        }
+    }
+}
+
+/// what `bad-apple encode` packs where
+pub struct EncodeOptions {
+    /// raw `monob` clip, `-` for stdin
+    pub input: String,
+    /// the `.bapl` clip to write
+    pub output: String,
+    pub size: Size,
+    pub fps: u16,
+}
+
+fn parse_size(val: &str) -> Option<Size> {
+    let (w, h) = val.split_once('x')?;
+    Some(Size::new(w.parse().ok()?, h.parse().ok()?))
+}
+
+/// comma separated list of numbers
+fn parse_list<T: FromStr>(val: &str) -> Option<Vec<T>> {
+    val.split(',').map(|v| v.parse().ok()).collect()
+}
+
+fn parse_rect(val: &str) -> Option<Rect> {
+    match parse_list(val)?[..] {
+        [x, y, w, h] => Some(Rect::new(x, y, w, h)),
+        _ => None,
+    }
+}
+
+/// whole black and white points, a positive gamma and a finite contrast
+fn parse_levels(val: &str) -> Option<Levels> {
+    let parts: Vec<&str> = val.split(',').collect();
+    let [black, white, rest @ ..] = &parts[..] else {
+        return None;
+    };
+    let (black, white) = (black.parse::<u8>().ok()?, white.parse::<u8>().ok()?);
+    let (gamma, contrast): (f32, f32) = match rest {
+        [] => (1.0, 1.0),
+        [g] => (g.parse().ok()?, 1.0),
+        [g, c] => (g.parse().ok()?, c.parse().ok()?),
+        _ => return None,
+    };
+    if black >= white || !gamma.is_finite() || gamma <= 0.0 || !contrast.is_finite() {
+        return None;
+    }
+    Some(Levels {
+        black,
+        white,
+        gamma,
+        contrast,
+    })
+}
+
+/// parses the arguments following the program name, the error is meant for the user
+pub fn parse<'a, I>(args: I) -> Result<Options, String>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    let mut opts = Options::default();
+
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--invert" => {
+                opts.pipeline.push(Transform::Invert);
+                continue;
+            }
+            "--profile" => {
+                opts.profile = true;
+                continue;
+            }
+            _ => {}
+        }
+
+        let Some(val) = iter.next() else {
+            continue;
+        };
+
+        match arg.as_str() {
+            "-i" | "--input" => opts.input = Some(val.clone()),
+            "--width" | "--height" => {
+                let v = val.parse().unwrap_or(0);
+                if !(1..=8192).contains(&v) {
+                    return Err(format!("{} is not in range [1, 8192]!", &arg[2..]));
+                }
+                if arg == "--width" {
+                    opts.size.w = v;
+                } else {
+                    opts.size.h = v;
+                }
+            }
+            "-s" | "--scale" => {
+                opts.scale = val.parse().unwrap_or(0.0);
+                if !(0.1..=100.0).contains(&opts.scale) {
+                    return Err("Scale is not in range [0.1, 100]!".into());
+                }
+            }
+            "-f" | "--fit" => match parse_size(val) {
+                Some(size) if size.flatten() > 0 => opts.fit = Some(size),
+                _ => return Err("Invalid fit, expected <columns>x<rows>".into()),
+            },
+            "--filter" => match val.as_str() {
+                "nearest" | "n" => opts.filter = Resample::Nearest,
+                "box" | "b" => opts.filter = Resample::Box,
+                "bilinear" | "l" => opts.filter = Resample::Bilinear,
+                _ => return Err("Invalid filter, not in {n, b, l, nearest, box, bilinear}".into()),
+            },
+            "--crop" => match parse_rect(val) {
+                Some(rect) if rect.w > 0 && rect.h > 0 => opts.pipeline.push(Transform::Crop(rect)),
+                _ => return Err("Invalid crop, expected <x>,<y>,<width>,<height>".into()),
+            },
+            "--flip" => match val.as_str() {
+                "h" => opts.pipeline.push(Transform::FlipH),
+                "v" => opts.pipeline.push(Transform::FlipV),
+                "hv" | "vh" => {
+                    opts.pipeline.push(Transform::FlipH);
+                    opts.pipeline.push(Transform::FlipV);
+                }
+                _ => return Err("Invalid flip, not in {h, v, hv}".into()),
+            },
+            "--rotate" => match val.as_str() {
+                "90" => opts.pipeline.push(Transform::Rotate(Rotation::R90)),
+                "180" => opts.pipeline.push(Transform::Rotate(Rotation::R180)),
+                "270" => opts.pipeline.push(Transform::Rotate(Rotation::R270)),
+                _ => return Err("Invalid rotation, not in {90, 180, 270}".into()),
+            },
+            "--pad" => match parse_size(val) {
+                Some(size) if size.flatten() > 0 => opts.pipeline.push(Transform::Pad(size)),
+                _ => return Err("Invalid pad, expected <width>x<height>".into()),
+            },
+            "--levels" => match parse_levels(val) {
+                Some(lv) => opts.filters.push(Filter::Levels(lv)),
+                None => {
+                    return Err(
+                        "Invalid levels, expected <black>,<white>[,<gamma>[,<contrast>]] \
+                        with whole points black < white in [0, 255] and gamma above 0"
+                            .into(),
+                    );
+                }
+            },
+            "--threshold" => match val.parse() {
+                Ok(t) => opts.filters.push(Filter::Threshold(t)),
+                Err(_) => return Err("Threshold is not in range [0, 255]!".into()),
+            },
+            "--adaptive" => match val.split_once(',').map(|(r, o)| (r.parse(), o.parse())) {
+                Some((Ok(radius @ 1..=MAX_RADIUS), Ok(offset @ -255..=255))) => {
+                    opts.filters.push(Filter::Adaptive { radius, offset })
+                }
+                _ => {
+                    return Err(format!(
+                        "Invalid adaptive threshold, expected <radius>,<offset> \
+                        with the radius in [1, {}] and the offset in [-255, 255]",
+                        MAX_RADIUS
+                    ));
+                }
+            },
+            "--edges" => match parse_list::<u8>(val).as_deref() {
+                Some(&[low, high]) if low <= high => opts.filters.push(Filter::Edges { low, high }),
+                _ => return Err("Invalid edges, expected <low>,<high> in [0, 255]".into()),
+            },
+            "--dither" => match val.as_str() {
+                "fs" => opts.filters.push(Filter::Dither(Dither::FloydSteinberg)),
+                "bayer2" => opts.filters.push(Filter::Dither(Dither::Bayer(2))),
+                "bayer4" => opts.filters.push(Filter::Dither(Dither::Bayer(4))),
+                "bayer8" => opts.filters.push(Filter::Dither(Dither::Bayer(8))),
+                _ => return Err("Invalid dither, not in {fs, bayer2, bayer4, bayer8}".into()),
+            },
+            "--dilate" | "--erode" => match val.parse() {
+                Ok(r @ 1..=MAX_RADIUS) if arg == "--dilate" => opts.filters.push(Filter::Dilate(r)),
+                Ok(r @ 1..=MAX_RADIUS) => opts.filters.push(Filter::Erode(r)),
+                _ => {
+                    return Err(format!(
+                        "Invalid radius for {}, expected one in [1, {}]",
+                        arg, MAX_RADIUS
+                    ));
+                }
+            },
+            "-r" | "--rate" => {
+                let fps = val.parse().unwrap_or(0);
+                if !(10..=120).contains(&fps) {
+                    return Err("Framerate is not in range [10, 120]!".into());
+                }
+                opts.fps = Some(fps);
+            }
+            "--csv" => opts.csv = Some(val.clone()),
+            "--workers" => match val.parse() {
+                Ok(n @ 1..=64) => opts.workers = Some(n),
+                _ => return Err("Workers is not in range [1, 64]!".into()),
+            },
+            "--buffer" => match val.parse() {
+                Ok(n @ 1..=1000) => opts.buffer = Some(n),
+                _ => return Err("Buffer is not in range [1, 1000] frames!".into()),
+            },
+            "-q" | "--quality" => match val.as_str() {
+                "extra" | "e" => opts.res = Some(Res::Extra),
+                "high" | "h" => opts.res = Some(Res::High),
+                "low" | "l" => opts.res = Some(Res::Low),
+                _ => return Err("Invalid quality, not in {e, h, l, extra, high, low}".into()),
+            },
+            _ => {}
+        }
+    }
+
+    Ok(opts)
+}
+
+/// parses the arguments following `encode`, the error is meant for the user
+pub fn parse_encode<'a, I>(args: I) -> Result<EncodeOptions, String>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    let mut input = None;
+    let mut output = None;
+    let mut size = Size::new(WIDTH, HEIGHT);
+    let mut fps = FPS as u16;
+
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        let Some(val) = iter.next() else {
+            continue;
+        };
+
+        match arg.as_str() {
+            "-i" | "--input" => input = Some(val.clone()),
+            "-o" | "--output" => output = Some(val.clone()),
+            "--width" | "--height" => {
+                let v = val.parse().unwrap_or(0);
+                if !(1..=8192).contains(&v) {
+                    return Err(format!("{} is not in range [1, 8192]!", &arg[2..]));
+                }
+                if arg == "--width" {
+                    size.w = v;
+                } else {
+                    size.h = v;
+                }
+            }
+            "-r" | "--rate" => {
+                fps = val.parse().unwrap_or(0);
+                if !(1..=240).contains(&fps) {
+                    return Err("Framerate is not in range [1, 240]!".into());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    match (input, output) {
+        (Some(input), Some(output)) => Ok(EncodeOptions {
+            input,
+            output,
+            size,
+            fps,
+        }),
+        _ => Err("Usage: bad-apple encode --input <raw> --output <bapl> \
+            [--width <w>] [--height <h>] [--rate <fps>]"
+            .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use renderer::filter::{Filter, FilterChain, Levels};
+
+    use super::{parse, parse_encode};
+    use crate::HEIGHT;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split(' ').map(String::from).collect()
+    }
+
+    #[test]
+    fn adaptive_offset_in_range() {
+        let opts = parse(&args("--adaptive 3,-20")).unwrap();
+        assert_eq!(
+            opts.filters,
+            FilterChain::new().then(Filter::Adaptive {
+                radius: 3,
+                offset: -20
+            })
+        );
+
+        assert!(parse(&args("--adaptive 3,-32768")).is_err());
+        assert!(parse(&args("--adaptive 3,256")).is_err());
+        assert!(parse(&args("--adaptive 0,5")).is_err());
+        assert!(parse(&args("--adaptive 3")).is_err());
+        assert!(parse(&args("--adaptive 65,0")).is_err());
+        assert!(parse(&args("--dilate 18446744073709551615")).is_err());
+        assert!(parse(&args("--erode 0")).is_err());
+        assert!(parse(&args("--erode 64")).is_ok());
+    }
+
+    #[test]
+    fn encode_options() {
+        let opts = parse_encode(&args("-i in.raw --output out.bapl --width 64 -r 24")).unwrap();
+        assert_eq!(
+            (opts.input.as_str(), opts.output.as_str()),
+            ("in.raw", "out.bapl")
+        );
+        assert_eq!((opts.size.w, opts.size.h, opts.fps), (64, HEIGHT, 24));
+
+        assert!(parse_encode(&args("--input in.raw")).is_err());
+        assert!(parse_encode(&args("-i in.raw -o out.bapl --height 0")).is_err());
+        assert!(parse_encode(&args("-i in.raw -o out.bapl --width 8193")).is_err());
+        assert!(parse_encode(&args("-i in.raw -o out.bapl --rate 241")).is_err());
+    }
+
+    #[test]
+    fn levels_are_checked_as_given() {
+        let opts = parse(&args("--levels 16,235,1.5")).unwrap();
+        assert_eq!(
+            opts.filters,
+            FilterChain::new().then(Filter::Levels(Levels {
+                black: 16,
+                white: 235,
+                gamma: 1.5,
+                contrast: 1.0
+            }))
+        );
+
+        assert!(parse(&args("--levels 254.9,255")).is_err());
+        assert!(parse(&args("--levels 200,100")).is_err());
+        assert!(parse(&args("--levels 0,256")).is_err());
+        assert!(parse(&args("--levels 0,255,0")).is_err());
+        assert!(parse(&args("--levels 0,255,-1")).is_err());
+        assert!(parse(&args("--levels 0,255,NaN")).is_err());
+        assert!(parse(&args("--levels 0,255,1,inf")).is_err());
+    }
+}
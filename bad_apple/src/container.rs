@@ -0,0 +1,519 @@
+//! `.bapl` clips: a header, length prefixed frame records and an index of their offsets
+//!
+//! ```text
+//! header  magic "BAPL", version u8, width u32, height u32, fps u16, frames u32, index offset u64
+//! record  kind u8, payload length u32, payload
+//! index   one u64 offset per frame
+//! ```
+//!
+//! All numbers are little endian. A payload holds the run lengths of alternating
+//! black and white pixels as LEB128 varints, starting with black. Key frames encode the
+//! pixels themselves, delta frames their XOR with the previous frame, whichever is smaller
+//! is written, except that every frame whose index is a multiple of 60 is a key frame,
+//! which is where seeking starts decoding. Neither side of a clip is larger than 8192.
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use renderer::{image::Image, types::Size};
+
+use crate::frame::Frame;
+
+pub const MAGIC: &[u8; 4] = b"BAPL";
+pub const VERSION: u8 = 1;
+const HEADER_LEN: u64 = 27;
+
+const KEY: u8 = 0;
+const DELTA: u8 = 1;
+
+/// frames at multiples of this are always key frames, seeking starts from them
+pub const KEY_INTERVAL: u32 = 60;
+/// widest and tallest clip read, larger headers are taken for corrupt
+const MAX_SIDE: usize = 8192;
+/// bytes of the longest varint
+const MAX_VARINT: usize = 10;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Header {
+    pub size: Size,
+    pub fps: u16,
+    pub frames: u32,
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+fn push_varint(out: &mut Vec<u8>, mut v: usize) {
+    while v >= 0x80 {
+        out.push(v as u8 | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+fn read_varint(data: &mut &[u8]) -> io::Result<usize> {
+    let mut v = 0usize;
+    for shift in (0..64).step_by(7) {
+        let (&b, rest) = data.split_first().ok_or_else(|| invalid("truncated run"))?;
+        *data = rest;
+        v |= ((b & 0x7F) as usize) << shift;
+        if b & 0x80 == 0 {
+            return Ok(v);
+        }
+    }
+    Err(invalid("run length overflows"))
+}
+
+/// run lengths of alternating values, starting with `false`
+fn rle_encode<I: Iterator<Item = bool>>(bits: I) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut current = false;
+    let mut run = 0;
+    for b in bits {
+        if b != current {
+            push_varint(&mut out, run);
+            current = b;
+            run = 0;
+        }
+        run += 1;
+    }
+    push_varint(&mut out, run);
+    out
+}
+
+fn rle_decode(mut data: &[u8], len: usize) -> io::Result<Vec<bool>> {
+    let mut out = Vec::with_capacity(len);
+    let mut current = false;
+    while !data.is_empty() {
+        let run = read_varint(&mut data)?;
+        if run > len - out.len() {
+            return Err(invalid("frame has too many pixels"));
+        }
+        out.extend(std::iter::repeat_n(current, run));
+        current = !current;
+    }
+    if out.len() != len {
+        return Err(invalid("frame has too few pixels"));
+    }
+    Ok(out)
+}
+
+/// encodes frames into a `.bapl` clip, the header is completed by `finish`
+pub struct Writer<W: Write + Seek> {
+    out: W,
+    header: Header,
+    index: Vec<u64>,
+    prev: Option<Vec<bool>>,
+    start: u64,
+    pos: u64,
+    keys: u32,
+}
+
+impl<W: Write + Seek> Writer<W> {
+    pub fn new(mut out: W, size: Size, fps: u16) -> io::Result<Self> {
+        if size.w > MAX_SIDE || size.h > MAX_SIDE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "clip is too large",
+            ));
+        }
+        let header = Header {
+            size,
+            fps,
+            frames: 0,
+        };
+        let start = out.stream_position()?;
+        write_header(&mut out, &header, 0)?;
+        Ok(Self {
+            out,
+            header,
+            index: Vec::new(),
+            prev: None,
+            start,
+            pos: start + HEADER_LEN,
+            keys: 0,
+        })
+    }
+
+    pub fn push(&mut self, frame: &Frame) -> io::Result<()> {
+        if frame.size != self.header.size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "frame size differs from the clip",
+            ));
+        }
+
+        let key = rle_encode(frame.pixels.iter().copied());
+        let delta = match &self.prev {
+            Some(prev) if !self.header.frames.is_multiple_of(KEY_INTERVAL) => Some(rle_encode(
+                frame.pixels.iter().zip(prev).map(|(a, b)| a ^ b),
+            )),
+            _ => None,
+        };
+        let (kind, payload) = match delta {
+            Some(delta) if delta.len() < key.len() => (DELTA, delta),
+            _ => {
+                self.keys += 1;
+                (KEY, key)
+            }
+        };
+
+        self.out.write_all(&[kind])?;
+        self.out.write_all(&(payload.len() as u32).to_le_bytes())?;
+        self.out.write_all(&payload)?;
+
+        self.index.push(self.pos);
+        self.pos += 5 + payload.len() as u64;
+        self.header.frames += 1;
+        self.prev = Some(frame.pixels.clone());
+        Ok(())
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.header.frames
+    }
+
+    /// frames written as key frames, forced or because they came out smaller
+    pub fn key_frames(&self) -> u32 {
+        self.keys
+    }
+
+    /// writes the index and the final header, returns the writer
+    pub fn finish(mut self) -> io::Result<W> {
+        let index_at = self.pos;
+        for offset in &self.index {
+            self.out.write_all(&offset.to_le_bytes())?;
+        }
+
+        self.out.seek(SeekFrom::Start(self.start))?;
+        write_header(&mut self.out, &self.header, index_at)?;
+        self.out.seek(SeekFrom::End(0))?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+fn write_header<W: Write>(out: &mut W, header: &Header, index_at: u64) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    out.write_all(&[VERSION])?;
+    out.write_all(&(header.size.w as u32).to_le_bytes())?;
+    out.write_all(&(header.size.h as u32).to_le_bytes())?;
+    out.write_all(&header.fps.to_le_bytes())?;
+    out.write_all(&header.frames.to_le_bytes())?;
+    out.write_all(&index_at.to_le_bytes())
+}
+
+/// decodes a `.bapl` clip front to back, seeking needs `R: Seek`
+pub struct Reader<R: Read> {
+    input: R,
+    header: Header,
+    index_at: u64,
+    prev: Vec<bool>,
+    next: u32,
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(mut input: R) -> io::Result<Self> {
+        let mut buf = [0u8; HEADER_LEN as usize];
+        input.read_exact(&mut buf)?;
+        if &buf[..4] != MAGIC {
+            return Err(invalid("not a bapl clip"));
+        }
+        if buf[4] != VERSION {
+            return Err(invalid("unsupported bapl version"));
+        }
+
+        let u32_at = |i: usize| u32::from_le_bytes(buf[i..i + 4].try_into().unwrap());
+        let header = Header {
+            size: Size::new(u32_at(5) as usize, u32_at(9) as usize),
+            fps: u16::from_le_bytes([buf[13], buf[14]]),
+            frames: u32_at(15),
+        };
+        if header.size.flatten() == 0 {
+            return Err(invalid("clip has no pixels"));
+        }
+        if header.size.w > MAX_SIDE || header.size.h > MAX_SIDE {
+            return Err(invalid("clip is too large"));
+        }
+
+        Ok(Self {
+            input,
+            header,
+            index_at: u64::from_le_bytes(buf[19..27].try_into().unwrap()),
+            prev: vec![false; header.size.flatten()],
+            next: 0,
+        })
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// index of the frame `next_frame` returns
+    pub fn position(&self) -> u32 {
+        self.next
+    }
+
+    pub fn next_frame(&mut self) -> io::Result<Option<Frame>> {
+        if self.next >= self.header.frames {
+            return Ok(None);
+        }
+
+        let mut head = [0u8; 5];
+        self.input.read_exact(&mut head)?;
+        let len = u32::from_le_bytes(head[1..].try_into().unwrap()) as usize;
+        // a run per pixel and a leading empty one is the most a frame can take
+        if len > (self.header.size.flatten() + 1) * MAX_VARINT {
+            return Err(invalid("frame record is too long"));
+        }
+        let mut payload = vec![0u8; len];
+        self.input.read_exact(&mut payload)?;
+
+        let bits = rle_decode(&payload, self.header.size.flatten())?;
+        match head[0] {
+            KEY => self.prev = bits,
+            DELTA => self.prev.iter_mut().zip(bits).for_each(|(p, d)| *p ^= d),
+            _ => return Err(invalid("unknown frame kind")),
+        }
+
+        self.next += 1;
+        Ok(Some(Image::new(self.header.size, self.prev.clone())))
+    }
+}
+
+impl<R: Read + Seek> Reader<R> {
+    /// positions the reader so `next_frame` returns frame `n`
+    pub fn seek(&mut self, n: u32) -> io::Result<()> {
+        let n = n.min(self.header.frames);
+        let key = n - n % KEY_INTERVAL;
+
+        // past the last frame there is nothing to read
+        let offset = if key < self.header.frames {
+            self.input
+                .seek(SeekFrom::Start(self.index_at + key as u64 * 8))?;
+            let mut buf = [0u8; 8];
+            self.input.read_exact(&mut buf)?;
+            u64::from_le_bytes(buf)
+        } else {
+            self.index_at
+        };
+
+        self.input.seek(SeekFrom::Start(offset))?;
+        if key < self.header.frames {
+            let mut kind = [0u8];
+            self.input.read_exact(&mut kind)?;
+            if kind[0] != KEY {
+                return Err(invalid("no key frame to seek from"));
+            }
+            self.input.seek(SeekFrom::Current(-1))?;
+        }
+        self.next = key;
+        while self.next < n {
+            self.next_frame()?;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for Reader<R> {
+    type Item = io::Result<Frame>;
+
+    fn next(&mut self) -> Option<io::Result<Frame>> {
+        self.next_frame().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Cursor};
+
+    use renderer::{image::Image, scale::Resample, types::Size};
+
+    use super::{DELTA, KEY, KEY_INTERVAL, Reader, Writer, rle_decode, rle_encode};
+    use crate::{
+        frame::{Frame, ToFrames},
+        source::Bits,
+    };
+
+    /// monob frames of a bar moving right, rows padded to whole bytes
+    fn raw_clip(size: Size, frames: usize) -> Vec<u8> {
+        let stride = size.w.div_ceil(8);
+        let mut raw = vec![0u8; stride * size.h * frames];
+        for f in 0..frames {
+            for y in 0..size.h {
+                let x = f % size.w;
+                raw[(f * size.h + y) * stride + x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+        raw
+    }
+
+    #[test]
+    fn rle_round_trip() {
+        let bits = [true, true, false, true, false, false, false];
+        let data = rle_encode(bits.iter().copied());
+        assert_eq!(data, [0, 2, 1, 1, 3]);
+        assert_eq!(rle_decode(&data, bits.len()).unwrap(), bits);
+        assert!(rle_decode(&data, 3).is_err());
+
+        let long = vec![false; 1000];
+        assert_eq!(
+            rle_decode(&rle_encode(long.into_iter()), 1000)
+                .unwrap()
+                .len(),
+            1000
+        );
+    }
+
+    #[test]
+    fn round_trip_raw_monob() {
+        let size = Size::new(61, 16);
+        let count = KEY_INTERVAL as usize + 10;
+        let raw = raw_clip(size, count);
+
+        let frames: Vec<Frame> = Bits::new(&raw[..], size.w)
+            .to_frames(size, size, Resample::Nearest)
+            .collect();
+        assert_eq!(frames.len(), count);
+
+        let mut writer = Writer::new(Cursor::new(Vec::new()), size, 30).unwrap();
+        for frame in &frames {
+            writer.push(frame).unwrap();
+        }
+        let data = writer.finish().unwrap().into_inner();
+        assert!(data.len() < raw.len());
+
+        let reader = Reader::new(Cursor::new(&data)).unwrap();
+        assert_eq!(reader.header().size, size);
+        assert_eq!(reader.header().fps, 30);
+        assert_eq!(reader.header().frames, count as u32);
+
+        let decoded: Vec<Frame> = reader.collect::<io::Result<_>>().unwrap();
+        assert_eq!(decoded.len(), count);
+        assert!(
+            decoded
+                .iter()
+                .zip(&frames)
+                .all(|(a, b)| a.pixels == b.pixels)
+        );
+
+        // a cut off record is an error, not the end of the clip
+        let records = u64::from_le_bytes(data[19..27].try_into().unwrap()) as usize;
+        let cut: Vec<_> = Reader::new(&data[..records - 1])
+            .unwrap()
+            .take(count)
+            .collect();
+        assert!(cut[..count - 1].iter().all(|f| f.is_ok()));
+        assert!(cut[count - 1].is_err());
+    }
+
+    #[test]
+    fn seek_to_delta_frame() {
+        let size = Size::new(8, 2);
+        let count = KEY_INTERVAL as usize * 2 + 5;
+        let frames: Vec<Frame> = Bits::new(&raw_clip(size, count)[..], size.w)
+            .to_frames(size, size, Resample::Nearest)
+            .collect();
+
+        let mut writer = Writer::new(Cursor::new(Vec::new()), size, 24).unwrap();
+        frames.iter().for_each(|f| writer.push(f).unwrap());
+        let mut reader = Reader::new(Cursor::new(writer.finish().unwrap().into_inner())).unwrap();
+
+        for n in [KEY_INTERVAL + 3, 2, count as u32 - 1] {
+            reader.seek(n).unwrap();
+            assert_eq!(reader.position(), n);
+            assert_eq!(
+                reader.next_frame().unwrap().unwrap().pixels,
+                frames[n as usize].pixels
+            );
+        }
+        reader.seek(count as u32 + 10).unwrap();
+        assert!(reader.next_frame().unwrap().is_none());
+
+        let wrong = Image::filled(Size::new(2, 2), false);
+        let mut writer = Writer::new(Cursor::new(Vec::new()), size, 24).unwrap();
+        assert!(writer.push(&wrong).is_err());
+    }
+
+    #[test]
+    fn keys_only_where_smaller() {
+        let size = Size::new(16, 4);
+        let count = KEY_INTERVAL as usize * 2 + 5;
+        let bars = Image::new(size, (0..size.flatten()).map(|i| i % 4 == 0).collect());
+        let black = Image::filled(size, false);
+
+        // a still clip is all deltas between the forced key frames, a clip that
+        // changes every pixel each frame is all key frames
+        let still: Vec<Frame> = vec![bars.clone(); count];
+        let flicker: Vec<Frame> = (0..count)
+            .map(|i| {
+                if i % 2 == 0 {
+                    black.clone()
+                } else {
+                    bars.clone()
+                }
+            })
+            .collect();
+        for (frames, keys) in [(still, 3), (flicker, count as u32)] {
+            let mut writer = Writer::new(Cursor::new(Vec::new()), size, 30).unwrap();
+            frames.iter().for_each(|f| writer.push(f).unwrap());
+            assert_eq!(writer.frames(), count as u32);
+            assert_eq!(writer.key_frames(), keys);
+
+            let mut reader =
+                Reader::new(Cursor::new(writer.finish().unwrap().into_inner())).unwrap();
+            for n in [count as u32 - 1, 0, KEY_INTERVAL + 1, 1] {
+                reader.seek(n).unwrap();
+                assert_eq!(
+                    reader.next_frame().unwrap().unwrap().pixels,
+                    frames[n as usize].pixels
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn corrupt_sizes_are_rejected() {
+        let size = Size::new(8, 2);
+        let mut writer = Writer::new(Cursor::new(Vec::new()), size, 24).unwrap();
+        writer.push(&Image::filled(size, true)).unwrap();
+        let data = writer.finish().unwrap().into_inner();
+
+        let mut huge = data.clone();
+        huge[5..9].copy_from_slice(&u32::MAX.to_le_bytes());
+        let err = Reader::new(Cursor::new(&huge)).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // the first record's payload length
+        let mut long = data.clone();
+        long[28..32].copy_from_slice(&u32::MAX.to_le_bytes());
+        let err = Reader::new(Cursor::new(&long))
+            .unwrap()
+            .next_frame()
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn seek_needs_key_frames() {
+        let size = Size::new(8, 2);
+        let bars = Image::new(size, (0..16).map(|i| i % 4 == 0).collect());
+        let mut writer = Writer::new(Cursor::new(Vec::new()), size, 24).unwrap();
+        for _ in 0..KEY_INTERVAL + 2 {
+            writer.push(&bars).unwrap();
+        }
+        let mut data = writer.finish().unwrap().into_inner();
+
+        // turn the forced key frame into a delta one
+        let index_at = u64::from_le_bytes(data[19..27].try_into().unwrap()) as usize;
+        let at = index_at + KEY_INTERVAL as usize * 8;
+        let record = u64::from_le_bytes(data[at..at + 8].try_into().unwrap()) as usize;
+        assert_eq!(data[record], KEY);
+        data[record] = DELTA;
+
+        let mut reader = Reader::new(Cursor::new(&data)).unwrap();
+        reader.seek(3).unwrap();
+        let err = reader.seek(KEY_INTERVAL + 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,187 @@
+use std::io::{self, Read};
+
+/// what a key asks the player to do
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Command {
+    Quit,
+    /// pauses or resumes
+    Pause,
+    /// frames forward or back, pauses
+    Step(i64),
+    /// seconds forward or back
+    Seek(i64),
+    /// to a tenth of the clip, 0 is its start
+    Jump(u8),
+    Faster,
+    Slower,
+}
+
+/// raw mode key presses as commands, other keys are skipped
+///
+/// `q`/Ctrl-C quit, space pauses, `.`/`,` step a frame, right/left seek 5s,
+/// up/down 30s, `0`-`9` jump to that tenth and `]`/`[` change the speed
+pub struct Keys<R: Read> {
+    input: R,
+    /// read after an Esc that did not start an arrow key
+    pending: Option<u8>,
+}
+
+impl<R: Read> Keys<R> {
+    pub fn new(input: R) -> Self {
+        Self {
+            input,
+            pending: None,
+        }
+    }
+
+    /// waits through reads that timed out
+    fn byte(&mut self) -> io::Result<u8> {
+        if let Some(b) = self.pending.take() {
+            return Ok(b);
+        }
+        let mut buf = [0u8; 1];
+        loop {
+            match self.input.read(&mut buf) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(_) => return Ok(buf[0]),
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted
+                    ) => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// the byte that came with the last one, `None` once a read times out or the
+    /// input ends
+    fn follow(&mut self) -> io::Result<Option<u8>> {
+        let mut buf = [0u8; 1];
+        loop {
+            match self.input.read(&mut buf) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(buf[0])),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// the rest of a control sequence after `ESC [`, its parameter and intermediate
+    /// bytes up to the final one, cut short by anything else
+    fn sequence(&mut self) -> io::Result<Vec<u8>> {
+        let mut seq = Vec::new();
+        while let Some(b) = self.follow()? {
+            seq.push(b);
+            if !(0x20..=0x3F).contains(&b) {
+                break;
+            }
+        }
+        Ok(seq)
+    }
+
+    /// blocks until a known key comes
+    fn command(&mut self) -> io::Result<Command> {
+        loop {
+            let cmd = match self.byte()? {
+                b'q' | 3 => Command::Quit,
+                b' ' => Command::Pause,
+                b'.' => Command::Step(1),
+                b',' => Command::Step(-1),
+                b']' => Command::Faster,
+                b'[' => Command::Slower,
+                d @ b'0'..=b'9' => Command::Jump(d - b'0'),
+                // arrow keys are `ESC [ A` to `ESC [ D` arriving at once, other
+                // sequences are dropped whole and a lone Esc leaves what is typed
+                // after it to be a key of its own
+                0x1B => match self.follow()? {
+                    Some(b'[') => match self.sequence()?.as_slice() {
+                        b"C" => Command::Seek(5),
+                        b"D" => Command::Seek(-5),
+                        b"A" => Command::Seek(30),
+                        b"B" => Command::Seek(-30),
+                        [] => Command::Slower,
+                        _ => continue,
+                    },
+                    next => {
+                        self.pending = next;
+                        continue;
+                    }
+                },
+                _ => continue,
+            };
+            return Ok(cmd);
+        }
+    }
+}
+
+impl<R: Read> Iterator for Keys<R> {
+    type Item = Command;
+
+    /// a read error ends the keys like a closed keyboard does
+    fn next(&mut self) -> Option<Command> {
+        self.command().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read};
+
+    use super::{Command, Keys};
+
+    /// gives the bytes one read at a time, `None` reads time out
+    struct Typed(Vec<Option<u8>>);
+
+    impl Read for Typed {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Ok(0);
+            }
+            match self.0.remove(0) {
+                Some(b) => {
+                    buf[0] = b;
+                    Ok(1)
+                }
+                None => Err(io::ErrorKind::WouldBlock.into()),
+            }
+        }
+    }
+
+    #[test]
+    fn keys_and_arrows() {
+        // Ctrl-Right, F5 and a late DA1 reply are no keys
+        let input: &[u8] = b" x.,\x1B[C\x1B[1;5C\x1B[B\x1B[15~\x1B[?62;4c\x1BO7][q";
+        let cmds: Vec<_> = Keys::new(input).collect();
+        assert_eq!(
+            cmds,
+            [
+                Command::Pause,
+                Command::Step(1),
+                Command::Step(-1),
+                Command::Seek(5),
+                Command::Seek(-30),
+                Command::Jump(7),
+                Command::Faster,
+                Command::Slower,
+                Command::Quit,
+            ]
+        );
+    }
+
+    #[test]
+    fn lone_escape() {
+        let esc = Some(0x1B);
+        let cmds = |typed: &[Option<u8>]| Keys::new(Typed(typed.to_vec())).collect::<Vec<_>>();
+
+        assert_eq!(cmds(&[esc, None, Some(b'q')]), [Command::Quit]);
+        assert_eq!(cmds(&[esc, Some(b'q')]), [Command::Quit]);
+        assert_eq!(cmds(&[esc, esc, Some(b' ')]), [Command::Pause]);
+        assert_eq!(
+            cmds(&[esc, Some(b'['), None, esc, Some(b'['), Some(b'D')]),
+            [Command::Slower, Command::Seek(-5)]
+        );
+    }
+}
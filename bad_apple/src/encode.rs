@@ -0,0 +1,60 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Seek},
+    time::Instant,
+};
+
+use renderer::scale::Resample;
+
+use crate::{
+    args::EncodeOptions,
+    container::Writer,
+    frame::ToFrames,
+    source::{Stream, raw_frames},
+};
+
+/// packs a raw `monob` clip into a `.bapl` one and reports how it went
+pub fn encode(opts: EncodeOptions) -> io::Result<()> {
+    let start = Instant::now();
+    let input: Box<dyn io::BufRead + Send> = match opts.input.as_str() {
+        "-" => Box::new(BufReader::new(io::stdin())),
+        path => Box::new(BufReader::new(File::open(path)?)),
+    };
+
+    let out = BufWriter::new(File::create(&opts.output)?);
+    let mut writer = Writer::new(out, opts.size, opts.fps)?;
+    let mut frames = Stream::new(raw_frames(input, opts.size)).to_frames(
+        opts.size,
+        opts.size,
+        Resample::Nearest,
+    );
+    for frame in frames.by_ref() {
+        writer.push(&frame)?;
+    }
+    if let Some(e) = frames.error() {
+        return Err(e);
+    }
+
+    let (frames, keys) = (writer.frames(), writer.key_frames());
+    // the whole file, index included
+    let bytes = writer.finish()?.stream_position()?;
+    let elapsed = start.elapsed();
+
+    let raw = (opts.size.w.div_ceil(8) * opts.size.h) as u64 * frames as u64;
+    println!(
+        "{} frames ({} key) of {}x{} at {} fps",
+        frames, keys, opts.size.w, opts.size.h, opts.fps
+    );
+    println!(
+        "{} -> {} bytes, ratio {:.1}:1",
+        raw,
+        bytes,
+        raw as f64 / bytes.max(1) as f64
+    );
+    println!(
+        "took {:.2}s, {:.0} frames/s",
+        elapsed.as_secs_f64(),
+        frames as f64 / elapsed.as_secs_f64()
+    );
+    Ok(())
+}
@@ -1,83 +1,275 @@
-use std::ops::Deref;
+use std::{
+    io,
+    time::{Duration, Instant},
+};
 
 use renderer::{
-    chunk_iter::{Collector, ToChunks},
-    traits::RenderTarget,
+    filter::FilterChain,
+    image::Image,
+    scale::{self, Resample, Sample},
+    transform::{Pipeline, Pixel},
+    types::Size,
 };
 
-struct Threshold(u16);
+pub type Frame = Image<bool>;
+
+/// pixel stream of whole frames that may be able to jump between them
+pub trait Source: Iterator + Send {
+    /// moves to the first pixel of `frame`, false when the source can only go on
+    fn seek(&mut self, _frame: usize) -> bool {
+        false
+    }
+
+    /// number of frames when known up front
+    fn frames(&self) -> Option<usize> {
+        None
+    }
+
+    /// why the pixels ended early, taken once, `None` when they reached the end
+    fn error(&mut self) -> Option<io::Error> {
+        None
+    }
+}
+
+impl<S: Source + ?Sized> Source for Box<S> {
+    fn seek(&mut self, frame: usize) -> bool {
+        (**self).seek(frame)
+    }
+
+    fn frames(&self) -> Option<usize> {
+        (**self).frames()
+    }
+
+    fn error(&mut self) -> Option<io::Error> {
+        (**self).error()
+    }
+}
+
+/// frame together with the time it took to produce, cut from the stream it is
+/// still in the clip's pixels and once prepared it may be rendered to text
+pub struct Timed<F = Frame> {
+    pub frame: F,
+    /// position of the frame in the clip
+    pub index: usize,
+    /// cutting the frame from the pixel stream
+    pub decode: Duration,
+    /// transforms, resampling and filters
+    pub scale: Duration,
+    /// turning the frame into glyphs, zero until it is
+    pub render: Duration,
+    /// how long the clip wants the frame shown, the frame rate decides otherwise
+    pub delay: Option<Duration>,
+}
+
+impl<F> Timed<F> {
+    /// the same frame in another form, the time `f` takes counts as rendering
+    pub fn map<G>(self, f: impl FnOnce(F) -> G) -> Timed<G> {
+        let start = Instant::now();
+        let frame = f(self.frame);
+        Timed {
+            frame,
+            index: self.index,
+            decode: self.decode,
+            scale: self.scale,
+            render: self.render + start.elapsed(),
+            delay: self.delay,
+        }
+    }
+}
+
+impl<F, E> Timed<Result<F, E>> {
+    /// the error of a step that failed in place of the frame
+    pub fn transpose(self) -> Result<Timed<F>, E> {
+        Ok(Timed {
+            frame: self.frame?,
+            index: self.index,
+            decode: self.decode,
+            scale: self.scale,
+            render: self.render,
+            delay: self.delay,
+        })
+    }
+}
+
+/// pixels a clip can be decoded to
+pub trait ClipPixel: Pixel + Sample + Send {
+    /// resamples a transformed frame to `dst` and turns it into a 1-bit frame
+    fn finish(frame: Image<Self>, dst: Size, filter: Resample, filters: &FilterChain) -> Frame;
+}
+
+impl ClipPixel for bool {
+    /// resampling works in grayscale when there are filters to run
+    fn finish(frame: Frame, dst: Size, filter: Resample, filters: &FilterChain) -> Frame {
+        if filters.is_empty() {
+            return scale::resize(&frame, dst, filter);
+        }
 
-impl Deref for Threshold {
-    type Target = u16;
-    fn deref(&self) -> &Self::Target {
-        &self.0
+        let gray = frame.map(|&b| if b { u8::MAX } else { 0 });
+        let gray = scale::resize(&gray, dst, filter);
+        filters.apply_gray(gray)
     }
 }
 
-impl Collector<bool> for Threshold {
-    fn new(_w: usize) -> Self {
-        Self(0)
+impl ClipPixel for u8 {
+    /// thresholded at mid gray unless the filters end in bits
+    fn finish(frame: Image<u8>, dst: Size, filter: Resample, filters: &FilterChain) -> Frame {
+        filters.apply_gray(scale::resize(&frame, dst, filter))
     }
-    fn push(&mut self, v: bool) {
-        self.0 += v as u16
+}
+
+/// what turns a frame cut from the clip into a 1-bit frame of the right size,
+/// kept apart from the stream so other threads can run it
+#[derive(Debug, Clone)]
+pub struct Processing {
+    dst: Size,
+    filter: Resample,
+    pipeline: Pipeline,
+    filters: FilterChain,
+}
+
+impl Processing {
+    /// transforms, resamples and filters a frame from `FramesIter::next_raw`
+    pub fn apply<T: ClipPixel>(&self, raw: Timed<Image<T>>) -> Timed {
+        let start = Instant::now();
+        let frame = T::finish(
+            self.pipeline.apply(raw.frame),
+            self.dst,
+            self.filter,
+            &self.filters,
+        );
+        Timed {
+            frame,
+            index: raw.index,
+            decode: raw.decode,
+            scale: start.elapsed(),
+            render: raw.render,
+            delay: raw.delay,
+        }
     }
-    fn empty(&mut self) {}
 }
 
 pub struct FramesIter<I>
 where
-    I: Iterator<Item = bool>,
+    I: Iterator,
+    I::Item: ClipPixel,
 {
-    w: usize,
-    h: usize,
+    src: Size,
+    processing: Processing,
     iter: I,
-    scale: usize,
-    threshold: u16,
+    /// index of the next frame
+    position: usize,
 }
 
-pub trait ToFrames: Iterator<Item = bool> + Sized {
-    fn to_frames(self, w: usize, h: usize, scale: usize) -> FramesIter<Self> {
-        FramesIter {
-            w,
-            h,
-            iter: self,
-            scale,
-            threshold: (scale.pow(2) / 2) as u16,
+impl<I> FramesIter<I>
+where
+    I: Iterator,
+    I::Item: ClipPixel,
+{
+    /// transforms every frame before it is resampled
+    pub fn transformed(mut self, pipeline: Pipeline) -> Self {
+        self.processing.pipeline = pipeline;
+        self
+    }
+
+    /// runs the filters on every resampled frame, 1-bit clips are then resampled in grayscale
+    pub fn filtered(mut self, filters: FilterChain) -> Self {
+        self.processing.filters = filters;
+        self
+    }
+
+    pub fn processing(&self) -> &Processing {
+        &self.processing
+    }
+
+    /// next frame as it is in the clip, left for `Processing::apply`
+    pub fn next_raw(&mut self) -> Option<Timed<Image<I::Item>>> {
+        let start = Instant::now();
+        let pixels: Vec<I::Item> = self.iter.by_ref().take(self.src.flatten()).collect();
+
+        if pixels.len() < self.src.flatten() {
+            return None;
         }
+        self.position += 1;
+        Some(Timed {
+            frame: Image::new(self.src, pixels),
+            index: self.position - 1,
+            decode: start.elapsed(),
+            scale: Duration::ZERO,
+            render: Duration::ZERO,
+            delay: None,
+        })
     }
-}
 
-impl<I> ToFrames for I where I: Iterator<Item = bool> {}
+    /// next frame with the time spent on it
+    pub fn next_timed(&mut self) -> Option<Timed> {
+        let raw = self.next_raw()?;
+        Some(self.processing.apply(raw))
+    }
+}
 
-impl<I> Iterator for FramesIter<I>
+impl<I> FramesIter<I>
 where
-    I: Iterator<Item = bool>,
+    I: Source,
+    I::Item: ClipPixel,
 {
-    type Item = Frame;
+    /// continues at `frame`, sources that cannot seek are decoded up to it,
+    /// false when the frame lies behind such a source
+    pub fn seek(&mut self, frame: usize) -> bool {
+        if self.iter.seek(frame) {
+            self.position = frame;
+            return true;
+        }
+        if frame < self.position {
+            return false;
+        }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let r_frame = self.iter.by_ref().take(self.w * self.h);
+        let skip = (frame - self.position) * self.src.flatten();
+        self.iter.by_ref().take(skip).for_each(drop);
+        self.position = frame;
+        true
+    }
 
-        let pixels: Vec<bool> = r_frame
-            .to_chunks::<Threshold>(self.w, self.scale, self.scale)
-            .map(|x| *x > self.threshold)
-            .collect();
+    /// why the clip ended early, see `Source::error`
+    pub fn error(&mut self) -> Option<io::Error> {
+        self.iter.error()
+    }
+}
 
-        if pixels.is_empty() {
-            return None;
+pub trait ToFrames: Iterator + Sized
+where
+    Self::Item: ClipPixel,
+{
+    /// cuts the pixel stream into `src` sized frames, resampled to `dst`
+    fn to_frames(self, src: Size, dst: Size, filter: Resample) -> FramesIter<Self> {
+        FramesIter {
+            src,
+            processing: Processing {
+                dst,
+                filter,
+                pipeline: Pipeline::new(),
+                filters: FilterChain::new(),
+            },
+            iter: self,
+            position: 0,
         }
-
-        Some(Frame(pixels))
     }
 }
 
-pub struct Frame(Vec<bool>);
+impl<I> ToFrames for I
+where
+    I: Iterator,
+    I::Item: ClipPixel,
+{
+}
+
+impl<I> Iterator for FramesIter<I>
+where
+    I: Iterator,
+    I::Item: ClipPixel,
+{
+    type Item = Frame;
 
-impl Frame {
-    pub fn draw_frame_to<R>(self, target: &mut R) -> Result<(), R::Error>
-    where
-        R: RenderTarget<bool>,
-    {
-        target.draw(self.0.into_iter())
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_timed().map(|t| t.frame)
     }
 }
@@ -1,134 +1,322 @@
-use bitvec::{order::Msb0, view::BitView};
-use raw_tty::IntoRawMode;
 use renderer::{
-    hr_bw_display::{HighResBWScreen, Res},
+    Error,
+    caps::Capabilities,
+    chart::{Chart, Histogram},
+    game_loop::GameLoop,
+    hr_bw_display::{Glyphs, HighResBWScreen},
+    image::Image,
+    scale,
     term_display::TermStatusLine,
     traits::RenderTarget,
+    types::Size,
 };
 use std::{
     env,
-    io::{self, Read},
-    sync::mpsc,
+    fs::File,
+    io::{self, BufWriter},
+    process,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, SyncSender},
+    },
     thread,
     time::{Duration, Instant},
 };
 
-use crate::frame::{Frame, ToFrames};
+use crate::{
+    args::Options,
+    controls::{Command, Keys},
+    frame::{ClipPixel, FramesIter, Source, Timed, ToFrames},
+    player::{Decoded, Player, Seek},
+    source::{Keyboard, Pixels},
+};
 
+mod args;
+mod container;
+mod controls;
+mod encode;
 mod frame;
+mod player;
+mod pool;
+mod source;
+mod y4m;
 
-const WIDTH: usize = 480;
-const HEIGHT: usize = 360;
-const APPLE: &[u8] = include_bytes!("../assets/apple480.raw");
-// const APPLE: &[u8] = include_bytes!("../assets/apple_short.raw");
-const FPS: u64 = 30;
+/// dimensions of the embedded clip, the default for others
+pub const WIDTH: usize = 480;
+pub const HEIGHT: usize = 360;
+pub const FPS: u64 = 30;
 
-fn play_apple(scale: usize, fps: u64, res: Res) -> io::Result<()> {
-    let w = WIDTH.div_ceil(scale);
+fn detect_caps(keyboard: Option<&Keyboard>) -> io::Result<Capabilities> {
+    let caps = Capabilities::from_env();
+    let Some(keyboard) = keyboard else {
+        return Ok(caps);
+    };
+    caps.query(
+        &mut keyboard.reader()?,
+        &mut io::stdout(),
+        Duration::from_millis(200),
+    )
+}
 
-    let mut screen = HighResBWScreen::new(w, res);
-    let mut statusline = TermStatusLine::new(w);
+/// frame interval chart and histogram in microseconds
+fn print_timings(timings: &[f64]) {
+    if timings.is_empty() {
+        return;
+    }
 
-    screen.init()?;
-    statusline.init()?;
+    println!("\nFrame interval [us]");
+    for row in Chart::new(Size::new(60, 12)).series(timings).rows() {
+        println!("{}", row);
+    }
+    println!();
+    for row in Histogram::new(timings, 8).rows(60) {
+        println!("{}", row);
+    }
+}
 
-    let (frames_tx, frames_rx) = mpsc::channel::<Frame>();
-    let (stop_tx, stop_rx) = mpsc::channel::<bool>();
+/// cuts frames for the workers and follows the player's seeks, publishing the latest
+/// epoch in `latest`, waits for a seek at the end of the clip and stops once the
+/// workers are gone, a clip that breaks off ends with its error
+fn decode<I>(
+    mut frames: FramesIter<I>,
+    delays: Vec<Option<Duration>>,
+    seeks: Receiver<Seek>,
+    latest: &AtomicU64,
+    tx: SyncSender<Decoded<Timed<Image<I::Item>>>>,
+) where
+    I: Source,
+    I::Item: ClipPixel,
+{
+    let mut epoch = 0;
+    loop {
+        // only the latest seek matters
+        if let Some(seek) = seeks.try_iter().last() {
+            frames.seek(seek.frame);
+            epoch = seek.epoch;
+            latest.store(epoch, Ordering::Relaxed);
+        }
+
+        let frame = frames.next_raw().map(|mut t| {
+            t.delay = delays.get(t.index).copied().flatten();
+            t
+        });
+        let end = frame.is_none();
+        // a broken clip ends with its error rather than like a whole one
+        let frame = match frames.error() {
+            Some(e) if end => Err(e.into()),
+            _ => Ok(frame),
+        };
+        if tx.send(Decoded { epoch, frame }).is_err() {
+            break;
+        }
+        if end {
+            let Ok(seek) = seeks.recv() else {
+                break;
+            };
+            frames.seek(seek.frame);
+            epoch = seek.epoch;
+            latest.store(epoch, Ordering::Relaxed);
+        }
+    }
+}
 
-    let input = thread::spawn(move || {
-        let mut raw_stdin = io::stdin().into_raw_mode().unwrap();
-        let mut buf = [0u8; 1];
+/// decodes on one thread and prepares frames down to their glyphs on `workers`
+/// others, the frames reach the player through `tx` in clip order
+fn prepare<I>(
+    frames: FramesIter<I>,
+    delays: Vec<Option<Duration>>,
+    seeks: Receiver<Seek>,
+    glyphs: Glyphs,
+    workers: usize,
+    tx: SyncSender<Decoded>,
+) where
+    I: Source + 'static,
+    I::Item: ClipPixel + 'static,
+{
+    let processing = frames.processing().clone();
+    let latest = Arc::new(AtomicU64::new(0));
+    let (jobs_tx, jobs_rx) = mpsc::sync_channel(workers);
 
-        loop {
-            raw_stdin.read_exact(&mut buf).unwrap();
+    let current = latest.clone();
+    pool::ordered(
+        jobs_rx,
+        tx,
+        workers,
+        move |job: Decoded<Timed<Image<_>>>| {
+            let stale = job.epoch != current.load(Ordering::Relaxed);
+            let frame = match job.frame {
+                Ok(Some(raw)) if !stale => processing
+                    .apply(raw)
+                    .map(|frame| glyphs.render(frame.pixels.into_iter()))
+                    .transpose()
+                    .map(Some),
+                // the player skips frames from before its last seek, they are not worth preparing
+                Ok(_) => Ok(None),
+                Err(e) => Err(e),
+            };
+            Decoded {
+                epoch: job.epoch,
+                frame,
+            }
+        },
+    );
+    thread::spawn(move || decode(frames, delays, seeks, &latest, jobs_tx));
+}
 
-            if buf[0] == b'q' || buf[0] == 3 {
-                stop_tx.send(true).unwrap();
-                break;
+fn play_apple(opts: Options) -> Result<(), Error> {
+    // without a terminal, e.g. under `ssh -T`, the clip plays without controls
+    let keyboard = match Keyboard::open() {
+        Ok(keyboard) => Some(keyboard),
+        Err(e) => {
+            eprintln!("Warning: no keyboard ({}), playing without controls", e);
+            None
+        }
+    };
+    let caps = detect_caps(keyboard.as_ref())?;
+    let res = match opts.res {
+        Some(res) => {
+            if let Err(e) = caps.check_res(&res) {
+                eprintln!("Warning: {}, the picture may be garbled", e);
             }
+            res
         }
-    });
-
-    thread::spawn(move || {
-        let _ = APPLE
-            .view_bits::<Msb0>()
-            .iter()
-            .map(|b| *b)
-            .to_frames(WIDTH, HEIGHT, scale)
-            .try_for_each(|frame| frames_tx.send(frame));
-    });
-
-    let mut frame_st = Instant::now();
-    let start = Instant::now();
+        None => caps.best_res(),
+    };
 
-    let sleep = Duration::from_micros(1_000_000 / fps);
+    let clip = source::open(opts.input.as_deref(), opts.size)?;
+    let fps = opts.fps.or(clip.fps).unwrap_or(FPS);
+    let still = clip.still;
+    let src = opts.pipeline.output_size(clip.size);
+    let dst = match opts.fit {
+        Some(cells) => {
+            let cell = res.to_size();
+            scale::fit(src, Size::new(cells.w * cell.w, cells.h * cell.h))
+        }
+        None => scale::scale_by(src, 1.0 / opts.scale),
+    };
+    let w = dst.w;
 
-    while let Ok(frame) = frames_rx.recv() {
-        frame.draw_frame_to(&mut screen)?;
-        // statusline.draw("Press q to exit!".chars())?;
+    let mut screen = HighResBWScreen::new(w, res)?.with_sync_output(caps.sync_output);
+    let mut statusline = TermStatusLine::new(w);
 
-        if stop_rx.try_recv().is_ok() {
-            break;
-        }
+    screen.init()?;
+    statusline.init()?;
+
+    // about a second of prepared frames unless asked otherwise, decoding waits for
+    // the player beyond that
+    let buffer = opts.buffer.unwrap_or(fps as usize);
+    let (frames_tx, frames_rx) = mpsc::sync_channel::<Decoded>(buffer);
+    let (seek_tx, seek_rx) = mpsc::channel::<Seek>();
+    let (cmd_tx, cmd_rx) = mpsc::channel::<Command>();
 
-        let delta = frame_st.elapsed();
-        statusline.draw(format!("dt: {}us     ", delta.as_micros()).chars())?;
+    if let Some(keyboard) = &keyboard {
+        let keys = Keys::new(keyboard.reader()?);
+        thread::spawn(move || {
+            for cmd in keys {
+                if cmd_tx.send(cmd).is_err() || cmd == Command::Quit {
+                    break;
+                }
+            }
+        });
+    }
 
-        thread::sleep(sleep.saturating_sub(delta));
-        frame_st = Instant::now();
+    let length = clip.pixels.frames();
+    let glyphs = screen.glyphs();
+    let workers = opts.workers.unwrap_or_else(pool::default_workers);
+    match clip.pixels {
+        Pixels::Bits(pixels) => prepare(
+            pixels
+                .to_frames(clip.size, dst, opts.filter)
+                .transformed(opts.pipeline)
+                .filtered(opts.filters),
+            clip.delays,
+            seek_rx,
+            glyphs,
+            workers,
+            frames_tx,
+        ),
+        Pixels::Gray(pixels) => prepare(
+            pixels
+                .to_frames(clip.size, dst, opts.filter)
+                .transformed(opts.pipeline)
+                .filtered(opts.filters),
+            clip.delays,
+            seek_rx,
+            glyphs,
+            workers,
+            frames_tx,
+        ),
     }
 
-    let total = start.elapsed();
+    let start = Instant::now();
 
-    input.join().unwrap();
+    let mut player = Player::new(screen, statusline, frames_rx, seek_tx, cmd_rx)
+        .with_overlay(opts.profile)
+        .with_hold(still)
+        .with_rate(fps)
+        .with_clip_rate(clip.fps)
+        .with_length(length);
+    // the player keeps to its own clock and skips late frames, the loop only paces it
+    GameLoop::from_rate(fps)
+        .with_max_catch_up(1)
+        .run(&mut player)?;
+    let total = start.elapsed();
 
-    screen.exit()?;
-    statusline.exit()?;
+    player.screen.exit()?;
+    player.statusline.exit()?;
+    // back out of raw mode before printing the report
+    drop(keyboard);
 
     println!("Total: {:.2}", total.as_secs_f64());
+    let dropped = player.profiler.dropped();
+    println!(
+        "Dropped: {} of {} frames",
+        dropped,
+        dropped as usize + player.profiler.frames()
+    );
+    print_timings(&player.timings());
+    println!();
+    for row in player.profiler.report() {
+        println!("{}", row);
+    }
+    if let Some(path) = opts.csv {
+        player
+            .profiler
+            .write_csv(BufWriter::new(File::create(path)?))?;
+    }
 
     Ok(())
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    let mut scale: usize = 1;
-    let mut fps: u64 = FPS;
-    let mut res: Res = Res::High;
-
-    let mut iter = args.iter().skip(1);
-    while let Some(arg) = iter.next() {
-        let Some(val) = iter.next() else {
-            continue;
-        };
 
-        match arg.as_str() {
-            "-s" | "--scale" => {
-                scale = val.parse().unwrap();
-                if !(0..=100).contains(&scale) {
-                    eprintln!("Scale is not in range [0, 100]!");
-                    return;
-                }
-            }
-            "-r" | "--rate" => {
-                fps = val.parse().unwrap();
-                if !(10..=120).contains(&fps) {
-                    eprintln!("Framerate is not in range [10, 120]!");
-                    return;
-                }
+    if args.get(1).is_some_and(|a| a == "encode") {
+        let opts = match args::parse_encode(args.iter().skip(2)) {
+            Ok(opts) => opts,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
             }
-            "-q" | "--quality" => match val.as_str() {
-                "extra" | "e" => res = Res::Extra,
-                "high" | "h" => res = Res::High,
-                "low" | "l" => res = Res::Low,
-                _ => {
-                    eprintln!("Invalid quality, not in {{e, h, l, extra, high, low}}");
-                    return;
-                }
-            },
-            _ => {}
+        };
+        if let Err(e) = encode::encode(opts) {
+            eprintln!("{}", e);
+            process::exit(1);
         }
+        return;
     }
 
-    play_apple(scale, fps, res).unwrap();
+    let opts = match args::parse(args.iter().skip(1)) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = play_apple(opts) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
 }
@@ -0,0 +1,506 @@
+use std::{
+    sync::mpsc::{Receiver, RecvTimeoutError, Sender},
+    time::Duration,
+};
+
+use renderer::{
+    Error, chart,
+    game_loop::{Clock, Control, Game, LoopStats, SystemClock},
+    hr_bw_display::HighResBWScreen,
+    profile::{Profiler, StageId},
+    term_display::TermStatusLine,
+    traits::RenderTarget,
+};
+
+use crate::{FPS, controls::Command, frame::Timed};
+
+/// frame times shown on the status line
+const SPARK_LEN: usize = 16;
+
+/// playback speeds `Faster` and `Slower` step through
+const SPEEDS: [f64; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+const NORMAL_SPEED: usize = 2;
+
+/// asks the decoder to go on from `frame`, frames it sends after carry `epoch`
+pub struct Seek {
+    pub epoch: u64,
+    pub frame: usize,
+}
+
+/// frame sent by the decoder, `None` at the end of the clip and an error ends it
+/// too, it reaches the player rendered to the text that draws it
+pub struct Decoded<F = Timed<String>> {
+    pub epoch: u64,
+    pub frame: Result<Option<F>, Error>,
+}
+
+/// plays decoded frames against its own clock, frames that are already late when
+/// taken are skipped so playback keeps to the clip's time
+pub struct Player<C: Clock = SystemClock> {
+    pub screen: HighResBWScreen,
+    pub statusline: TermStatusLine,
+    pub profiler: Profiler,
+    stages: [StageId; 4],
+    /// profiler line instead of the frame time on the status line
+    overlay: bool,
+    /// keeps the last frame on screen until stopped once the clip ends
+    hold: bool,
+    decoded: Receiver<Decoded>,
+    seeks: Sender<Seek>,
+    commands: Receiver<Command>,
+    /// frames per second played
+    fps: u64,
+    /// frames per second of the clip itself, the time of a frame goes by it
+    clip_fps: Option<u64>,
+    /// frames in the clip if known
+    length: Option<usize>,
+    /// frames older than the last seek are skipped
+    epoch: u64,
+    paused: bool,
+    /// frames to take while paused
+    steps: u64,
+    speed: usize,
+    /// nothing comes until the next seek
+    ended: bool,
+    /// what ended the clip early, returned once the frames before it are shown
+    failed: Option<Error>,
+    /// the end of the clip came while catching up
+    finished: bool,
+    clock: C,
+    /// when the next frame is to be shown, unset until playback (re)starts
+    due: Option<Duration>,
+    frame: Option<Timed<String>>,
+    shown: bool,
+    /// the status line changed without a new frame
+    status: bool,
+}
+
+impl Player {
+    pub fn new(
+        screen: HighResBWScreen,
+        statusline: TermStatusLine,
+        decoded: Receiver<Decoded>,
+        seeks: Sender<Seek>,
+        commands: Receiver<Command>,
+    ) -> Self {
+        Self::with_clock(
+            screen,
+            statusline,
+            decoded,
+            seeks,
+            commands,
+            SystemClock::default(),
+        )
+    }
+}
+
+impl<C: Clock> Player<C> {
+    pub fn with_clock(
+        screen: HighResBWScreen,
+        statusline: TermStatusLine,
+        decoded: Receiver<Decoded>,
+        seeks: Sender<Seek>,
+        commands: Receiver<Command>,
+        clock: C,
+    ) -> Self {
+        let mut profiler = Profiler::new();
+        let stages = ["decode", "scale", "render", "write"].map(|s| profiler.stage(s));
+        Self {
+            screen,
+            statusline,
+            profiler,
+            stages,
+            overlay: false,
+            hold: false,
+            decoded,
+            seeks,
+            commands,
+            fps: FPS,
+            clip_fps: None,
+            length: None,
+            epoch: 0,
+            paused: false,
+            steps: 0,
+            speed: NORMAL_SPEED,
+            ended: false,
+            failed: None,
+            finished: false,
+            clock,
+            due: None,
+            frame: None,
+            shown: true,
+            status: false,
+        }
+    }
+
+    pub fn with_overlay(mut self, overlay: bool) -> Self {
+        self.overlay = overlay;
+        self
+    }
+
+    pub fn with_hold(mut self, hold: bool) -> Self {
+        self.hold = hold;
+        self
+    }
+
+    /// frames per second to play at, frames without a delay of their own stay that long
+    pub fn with_rate(mut self, fps: u64) -> Self {
+        self.fps = fps.max(1);
+        self
+    }
+
+    /// frames per second the clip was made at, seeks by time and the clock go by
+    /// it rather than by the playing rate when known
+    pub fn with_clip_rate(mut self, fps: Option<u64>) -> Self {
+        self.clip_fps = fps.filter(|&fps| fps > 0);
+        self
+    }
+
+    /// frames in a second of the clip
+    fn time_rate(&self) -> u64 {
+        self.clip_fps.unwrap_or(self.fps)
+    }
+
+    /// frames in the clip, needed to jump to a part of it
+    pub fn with_length(mut self, length: Option<usize>) -> Self {
+        self.length = length;
+        self
+    }
+
+    /// intervals between shown frames in microseconds
+    pub fn timings(&self) -> Vec<f64> {
+        self.profiler
+            .intervals()
+            .iter()
+            .map(|d| d.as_micros() as f64)
+            .collect()
+    }
+
+    /// index of the current frame
+    fn position(&self) -> usize {
+        self.frame.as_ref().map_or(0, |t| t.index)
+    }
+
+    /// restarts decoding at `frame`, a paused player shows just that frame
+    fn seek(&mut self, frame: i64) {
+        let last = self.length.map_or(i64::MAX, |len| len as i64 - 1);
+        let frame = frame.clamp(0, last.max(0)) as usize;
+        self.epoch += 1;
+        // a gone decoder shows up as the end of the clip on the next update
+        let _ = self.seeks.send(Seek {
+            epoch: self.epoch,
+            frame,
+        });
+        self.ended = false;
+        self.finished = false;
+        self.due = None;
+        // the frame taken before the seek is no dropped frame
+        self.shown = true;
+        if self.paused {
+            self.steps = 1;
+        }
+    }
+
+    fn command(&mut self, cmd: Command) {
+        let pos = self.position() as i64;
+        match cmd {
+            Command::Quit => {}
+            Command::Pause => {
+                self.paused = !self.paused;
+                self.steps = 0;
+                self.due = None;
+            }
+            Command::Step(n) => {
+                self.paused = true;
+                self.due = None;
+                if n > 0 {
+                    self.steps += n as u64;
+                } else {
+                    self.seek(pos + n);
+                }
+            }
+            Command::Seek(secs) => self.seek(pos + secs * self.time_rate() as i64),
+            Command::Jump(tenth) => {
+                if let Some(len) = self.length {
+                    self.seek((len * tenth as usize / 10) as i64);
+                }
+            }
+            Command::Faster => self.speed = (self.speed + 1).min(SPEEDS.len() - 1),
+            Command::Slower => self.speed = self.speed.saturating_sub(1),
+        }
+        self.status = true;
+    }
+
+    /// how long `frame` stays on screen at the chosen speed
+    fn step_of(&self, frame: &Timed<String>) -> Duration {
+        frame
+            .delay
+            .unwrap_or(Duration::from_nanos(1_000_000_000 / self.fps))
+            .div_f64(SPEEDS[self.speed])
+    }
+
+    /// next frame since the last seek waiting up to `wait` for it, `Some(None)` at
+    /// the end of the clip or on an error and `None` when nothing came in time
+    fn receive(&mut self, wait: Duration) -> Option<Option<Timed<String>>> {
+        loop {
+            let decoded = match self.decoded.recv_timeout(wait) {
+                Ok(decoded) => decoded,
+                Err(RecvTimeoutError::Timeout) => return None,
+                // a gone decoder ends the clip
+                Err(RecvTimeoutError::Disconnected) => return Some(None),
+            };
+            // decoded before the last seek
+            if decoded.epoch != self.epoch {
+                continue;
+            }
+            return match decoded.frame {
+                Ok(frame) => Some(frame),
+                Err(e) => {
+                    self.failed = Some(e);
+                    Some(None)
+                }
+            };
+        }
+    }
+
+    /// stops or, holding, keeps the last frame until a seek, fails if decoding did
+    fn end(&mut self) -> Result<Control, Error> {
+        if let Some(e) = self.failed.take() {
+            return Err(e);
+        }
+        if !self.hold {
+            return Ok(Control::Stop);
+        }
+        self.ended = true;
+        Ok(Control::Continue)
+    }
+
+    /// position, speed and pause state for the status line
+    fn state(&self) -> String {
+        let time = |frames: usize| {
+            let secs = frames as u64 / self.time_rate();
+            format!("{}:{:02}", secs / 60, secs % 60)
+        };
+
+        let mut out = time(self.position());
+        if let Some(len) = self.length {
+            out += &format!("/{}", time(len));
+        }
+        if self.speed != NORMAL_SPEED {
+            out += &format!(" {}x", SPEEDS[self.speed]);
+        }
+        if self.paused {
+            out += " paused";
+        }
+        out
+    }
+}
+
+impl<C: Clock> Game for Player<C> {
+    type Error = Error;
+
+    fn poll(&mut self) -> Control {
+        while let Ok(cmd) = self.commands.try_recv() {
+            if cmd == Command::Quit {
+                return Control::Stop;
+            }
+            self.command(cmd);
+        }
+        Control::Continue
+    }
+
+    fn update(&mut self, step: Duration) -> Result<Control, Error> {
+        if self.finished {
+            self.finished = false;
+            return self.end();
+        }
+        if self.ended || (self.paused && self.steps == 0) {
+            return Ok(Control::Continue);
+        }
+
+        // waiting a step at most, so commands still come through while the input
+        // stalls, e.g. a slow pipe
+        let Some(frame) = self.receive(step) else {
+            return Ok(Control::Continue);
+        };
+        let Some(mut frame) = frame else {
+            return self.end();
+        };
+        if !self.shown {
+            self.profiler.drop_frame();
+        }
+
+        // a frame whose successor is due already is decoded but never drawn
+        let now = self.clock.now();
+        let mut due = self.due.unwrap_or(now);
+        while !self.paused && due + self.step_of(&frame) <= now {
+            match self.receive(Duration::ZERO) {
+                Some(Some(next)) => {
+                    due += self.step_of(&frame);
+                    frame = next;
+                    self.profiler.drop_frame();
+                }
+                Some(None) => {
+                    self.finished = true;
+                    break;
+                }
+                None => break,
+            }
+        }
+        self.due = Some(due + self.step_of(&frame));
+        self.frame = Some(frame);
+        self.shown = false;
+        self.steps = self.steps.saturating_sub(1);
+        Ok(Control::Continue)
+    }
+
+    /// the clip's delay for the frame just taken, at the chosen speed
+    fn next_step(&self) -> Option<Duration> {
+        self.frame.as_ref().map(|t| self.step_of(t))
+    }
+
+    fn render(&mut self, _alpha: f32, _stats: &LoopStats) -> Result<(), Error> {
+        if let Some(timed) = self.frame.as_ref().filter(|_| !self.shown) {
+            let [decode, scale, render, write] = self.stages;
+
+            self.screen.write_rendered(&timed.frame)?;
+
+            self.profiler.record(decode, timed.decode);
+            self.profiler.record(scale, timed.scale);
+            self.profiler.record(render, timed.render);
+            self.profiler.record(write, self.screen.last_write());
+            self.profiler.frame();
+            self.shown = true;
+        } else if !self.status {
+            return Ok(());
+        }
+        self.status = false;
+
+        let line = if self.overlay {
+            self.profiler.overlay()
+        } else {
+            let intervals = self.profiler.intervals();
+            let recent: Vec<f64> = intervals[intervals.len().saturating_sub(SPARK_LEN)..]
+                .iter()
+                .map(|d| d.as_micros() as f64)
+                .collect();
+            format!(
+                "dt: {:>6}us {}",
+                intervals.last().unwrap_or(&Duration::ZERO).as_micros(),
+                chart::sparkline(&recent, SPARK_LEN)
+            )
+        };
+        let line = format!("{} | {}", self.state(), line);
+        self.statusline.draw(line.chars())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, time::Duration};
+
+    use renderer::{
+        game_loop::{Control, Game, ManualClock},
+        hr_bw_display::{HighResBWScreen, Res},
+        term_display::TermStatusLine,
+    };
+
+    use super::{Decoded, Player};
+    use crate::{controls::Command, frame::Timed};
+
+    fn decoded(index: usize) -> Decoded {
+        Decoded {
+            epoch: 0,
+            frame: Ok(Some(Timed {
+                frame: String::new(),
+                index,
+                decode: Duration::ZERO,
+                scale: Duration::ZERO,
+                render: Duration::ZERO,
+                delay: None,
+            })),
+        }
+    }
+
+    #[test]
+    fn late_frames_are_skipped() {
+        let (tx, rx) = mpsc::sync_channel(4);
+        let (seeks, _) = mpsc::channel();
+        let (_, commands) = mpsc::channel();
+        let mut player = Player::with_clock(
+            HighResBWScreen::new(4, Res::Low).unwrap(),
+            TermStatusLine::new(4),
+            rx,
+            seeks,
+            commands,
+            ManualClock::default(),
+        )
+        .with_rate(10);
+        for i in 0..4 {
+            tx.send(decoded(i)).unwrap();
+        }
+
+        player.update(Duration::ZERO).unwrap();
+        assert_eq!((player.position(), player.profiler.dropped()), (0, 0));
+        // as if drawn
+        player.shown = true;
+
+        // frame 1 was due at 100ms and frame 2 at 200ms, 1 is decoded but never drawn
+        player.clock.now = Duration::from_millis(250);
+        player.update(Duration::ZERO).unwrap();
+        assert_eq!((player.position(), player.profiler.dropped()), (2, 1));
+        assert_eq!(player.next_step(), Some(Duration::from_millis(100)));
+        player.shown = true;
+
+        // on time again
+        player.clock.now = Duration::from_millis(300);
+        player.update(Duration::ZERO).unwrap();
+        assert_eq!((player.position(), player.profiler.dropped()), (3, 1));
+    }
+
+    #[test]
+    fn commands_during_a_stall() {
+        let (tx, rx) = mpsc::sync_channel(4);
+        let (seeks, _) = mpsc::channel();
+        let (cmd_tx, commands) = mpsc::channel();
+        let mut player = Player::with_clock(
+            HighResBWScreen::new(4, Res::Low).unwrap(),
+            TermStatusLine::new(4),
+            rx,
+            seeks,
+            commands,
+            ManualClock::default(),
+        );
+
+        // nothing decoded yet, the update gives up after its step
+        let ctrl = player.update(Duration::from_millis(10)).unwrap();
+        assert_eq!(ctrl, Control::Continue);
+        cmd_tx.send(Command::Quit).unwrap();
+        assert_eq!(player.poll(), Control::Stop);
+
+        drop(tx);
+        assert_eq!(player.update(Duration::ZERO).unwrap(), Control::Stop);
+    }
+
+    #[test]
+    fn time_follows_the_clip_rate() {
+        let (_tx, rx) = mpsc::sync_channel(4);
+        let (seeks, seek_rx) = mpsc::channel();
+        let (_, commands) = mpsc::channel();
+        let mut player = Player::with_clock(
+            HighResBWScreen::new(4, Res::Low).unwrap(),
+            TermStatusLine::new(4),
+            rx,
+            seeks,
+            commands,
+            ManualClock::default(),
+        )
+        .with_rate(60)
+        .with_clip_rate(Some(30))
+        .with_length(Some(600));
+
+        player.command(Command::Seek(5));
+        assert_eq!(seek_rx.try_recv().unwrap().frame, 150);
+        assert!(player.state().starts_with("0:00/0:20"));
+    }
+}
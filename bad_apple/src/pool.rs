@@ -0,0 +1,133 @@
+use std::{
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Receiver, SyncSender},
+    },
+    thread,
+};
+
+/// number of workers when not asked for, one core is left to the player
+pub fn default_workers() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get().saturating_sub(1).clamp(1, 4))
+}
+
+/// runs `work` over `jobs` on `workers` threads and sends the results to `out` in the
+/// order the jobs came in, the threads end once `jobs` does or `out` is gone
+///
+/// every job taken leaves a slot for its result in a queue of `workers` slots, so
+/// while the oldest job is still running at most `workers` more are started
+pub fn ordered<T, U, F>(jobs: Receiver<T>, out: SyncSender<U>, workers: usize, work: F)
+where
+    T: Send + 'static,
+    U: Send + 'static,
+    F: Fn(T) -> U + Send + Sync + 'static,
+{
+    let workers = workers.max(1);
+    let jobs = Arc::new(Mutex::new(jobs));
+    let work = Arc::new(work);
+    let (slots_tx, slots_rx) = mpsc::sync_channel::<Receiver<U>>(workers);
+
+    for _ in 0..workers {
+        let (jobs, work, slots) = (jobs.clone(), work.clone(), slots_tx.clone());
+        thread::spawn(move || {
+            loop {
+                // slots are queued under the same lock the jobs are taken with
+                let (job, done) = {
+                    let jobs = jobs.lock().unwrap();
+                    let Ok(job) = jobs.recv() else {
+                        break;
+                    };
+                    let (done, slot) = mpsc::sync_channel(1);
+                    if slots.send(slot).is_err() {
+                        break;
+                    }
+                    (job, done)
+                };
+                if done.send(work(job)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    drop(slots_tx);
+
+    thread::spawn(move || {
+        for slot in slots_rx {
+            // a worker that died leaves its slot empty, the results after it are lost
+            let Ok(result) = slot.recv() else {
+                return;
+            };
+            if out.send(result).is_err() {
+                return;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            Arc, Mutex,
+            atomic::{AtomicU64, Ordering},
+            mpsc,
+        },
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use super::ordered;
+
+    #[test]
+    fn results_keep_job_order() {
+        let (jobs_tx, jobs_rx) = mpsc::sync_channel(2);
+        let (out_tx, out_rx) = mpsc::sync_channel(2);
+        ordered(jobs_rx, out_tx, 4, |n: u64| {
+            // later jobs tend to finish first
+            thread::sleep(Duration::from_micros((7 - n % 8) * 100));
+            n * 2
+        });
+
+        thread::spawn(move || {
+            for n in 0..50 {
+                jobs_tx.send(n).unwrap();
+            }
+        });
+        let results: Vec<u64> = out_rx.iter().collect();
+        assert_eq!(results, (0..50).map(|n| n * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn stalled_job_bounds_the_rest() {
+        let (jobs_tx, jobs_rx) = mpsc::channel();
+        let (out_tx, out_rx) = mpsc::sync_channel(1);
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let release = Mutex::new(release_rx);
+        let started = Arc::new(AtomicU64::new(0));
+
+        let counter = started.clone();
+        ordered(jobs_rx, out_tx, 3, move |n: u64| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            if n == 0 {
+                release.lock().unwrap().recv().unwrap();
+            }
+            n
+        });
+        for n in 0..100 {
+            jobs_tx.send(n).unwrap();
+        }
+        drop(jobs_tx);
+
+        // the stalled job and one per slot, nothing beyond
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while started.load(Ordering::SeqCst) < 4 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(1));
+        }
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(started.load(Ordering::SeqCst), 4);
+
+        release_tx.send(()).unwrap();
+        let results: Vec<u64> = out_rx.iter().collect();
+        assert_eq!(results, (0..100).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,780 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Bytes, Cursor, IsTerminal, Read, Seek, SeekFrom, Stdin},
+    iter,
+    os::fd::AsFd,
+    path::{Path, PathBuf},
+    time::Duration,
+    vec,
+};
+
+use raw_tty::{GuardMode, Termios, TtyWithGuard};
+use renderer::{
+    gif,
+    image::luma,
+    pnm::{self, Pnm},
+    types::Size,
+};
+
+use crate::{container, frame::Source, y4m};
+
+/// pixels of a `monob` raw video, rows start on a byte boundary so any padding
+/// at the end of a row is skipped
+pub struct Bits<R: BufRead> {
+    bytes: Bytes<R>,
+    width: usize,
+    x: usize,
+    byte: u8,
+    left: u8,
+}
+
+impl<R: BufRead> Bits<R> {
+    pub fn new(input: R, width: usize) -> Self {
+        Self {
+            bytes: input.bytes(),
+            width,
+            x: 0,
+            byte: 0,
+            left: 0,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Bits<R> {
+    type Item = bool;
+
+    /// a read error ends the stream like the end of the file does
+    fn next(&mut self) -> Option<bool> {
+        if self.x == self.width {
+            self.x = 0;
+            self.left = 0;
+        }
+        if self.left == 0 {
+            self.byte = self.bytes.next()?.ok()?;
+            self.left = 8;
+        }
+
+        self.left -= 1;
+        self.x += 1;
+        Some(self.byte >> self.left & 1 == 1)
+    }
+}
+
+/// frames loaded one at a time by index, so any of them can come next
+pub struct Images<T> {
+    load: Box<dyn FnMut(usize) -> io::Result<Option<Vec<T>>> + Send>,
+    frames: Option<usize>,
+    next: usize,
+    current: vec::IntoIter<T>,
+    error: Option<io::Error>,
+}
+
+impl<T> Images<T> {
+    /// `load` gives the pixels of a frame, `None` past the end
+    pub fn new<F>(frames: Option<usize>, load: F) -> Self
+    where
+        F: FnMut(usize) -> io::Result<Option<Vec<T>>> + Send + 'static,
+    {
+        Self {
+            load: Box::new(load),
+            frames,
+            next: 0,
+            current: Vec::new().into_iter(),
+            error: None,
+        }
+    }
+}
+
+impl<T> Iterator for Images<T> {
+    type Item = T;
+
+    /// a frame that fails to load ends the pixels, the error is kept for `Source::error`
+    fn next(&mut self) -> Option<T> {
+        loop {
+            if let Some(px) = self.current.next() {
+                return Some(px);
+            }
+            match (self.load)(self.next) {
+                Ok(frame) => self.current = frame?.into_iter(),
+                Err(e) => {
+                    self.error = Some(e);
+                    return None;
+                }
+            }
+            self.next += 1;
+        }
+    }
+}
+
+impl<T: Send> Source for Images<T> {
+    fn seek(&mut self, frame: usize) -> bool {
+        self.next = frame;
+        self.current = Vec::new().into_iter();
+        true
+    }
+
+    fn frames(&self) -> Option<usize> {
+        self.frames
+    }
+
+    fn error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+}
+
+/// reads the pixels of the next frame, `None` at the end
+type ReadFrame<T> = Box<dyn FnMut() -> io::Result<Option<Vec<T>>> + Send>;
+
+/// frames that can only be read front to back
+pub struct Stream<T> {
+    /// gone once the stream ended
+    read: Option<ReadFrame<T>>,
+    current: vec::IntoIter<T>,
+    error: Option<io::Error>,
+}
+
+impl<T> Stream<T> {
+    /// `read` gives the pixels of the next frame, `None` at the end
+    pub fn new<F>(read: F) -> Self
+    where
+        F: FnMut() -> io::Result<Option<Vec<T>>> + Send + 'static,
+    {
+        Self {
+            read: Some(Box::new(read)),
+            current: Vec::new().into_iter(),
+            error: None,
+        }
+    }
+}
+
+impl<T> Iterator for Stream<T> {
+    type Item = T;
+
+    /// ends for good at the end of the stream or at its first error, which is
+    /// kept for `Source::error`
+    fn next(&mut self) -> Option<T> {
+        loop {
+            if let Some(px) = self.current.next() {
+                return Some(px);
+            }
+            match (self.read.as_mut()?)() {
+                Ok(Some(frame)) => self.current = frame.into_iter(),
+                Ok(None) => self.read = None,
+                Err(e) => {
+                    self.read = None;
+                    self.error = Some(e);
+                }
+            }
+        }
+    }
+}
+
+impl<T: Send> Source for Stream<T> {
+    fn error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+}
+
+/// `monob` frames of `size` one after another, a partial frame at the end is
+/// dropped like it is for files
+pub fn raw_frames<R>(
+    mut input: R,
+    size: Size,
+) -> impl FnMut() -> io::Result<Option<Vec<bool>>> + Send
+where
+    R: Read + Send,
+{
+    let mut frame = vec![0u8; size.w.div_ceil(8) * size.h];
+    move || match input.read_exact(&mut frame) {
+        Ok(()) => Ok(Some(Bits::new(&frame[..], size.w).collect())),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// pixel stream of a clip, 1-bit or grayscale
+pub enum Pixels {
+    Bits(Box<dyn Source<Item = bool>>),
+    Gray(Box<dyn Source<Item = u8>>),
+}
+
+impl Pixels {
+    pub fn frames(&self) -> Option<usize> {
+        match self {
+            Self::Bits(pixels) => pixels.frames(),
+            Self::Gray(pixels) => pixels.frames(),
+        }
+    }
+}
+
+/// where a clip is read from, everything but stdin can seek
+enum Input {
+    Stdin(BufReader<Stdin>),
+    File(BufReader<File>),
+    /// the embedded clip
+    #[cfg_attr(not(feature = "embedded"), allow(dead_code))]
+    Memory(Cursor<&'static [u8]>),
+}
+
+impl Input {
+    /// `None` for streams
+    fn byte_len(&self) -> Option<u64> {
+        match self {
+            Self::Stdin(_) => None,
+            Self::File(file) => file.get_ref().metadata().ok().map(|m| m.len()),
+            Self::Memory(data) => Some(data.get_ref().len() as u64),
+        }
+    }
+}
+
+impl Read for Input {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Stdin(input) => input.read(buf),
+            Self::File(input) => input.read(buf),
+            Self::Memory(input) => input.read(buf),
+        }
+    }
+}
+
+impl BufRead for Input {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match self {
+            Self::Stdin(input) => input.fill_buf(),
+            Self::File(input) => input.fill_buf(),
+            Self::Memory(input) => input.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        match self {
+            Self::Stdin(input) => input.consume(amt),
+            Self::File(input) => input.consume(amt),
+            Self::Memory(input) => input.consume(amt),
+        }
+    }
+}
+
+impl Seek for Input {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            Self::Stdin(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "stdin cannot seek",
+            )),
+            Self::File(input) => input.seek(pos),
+            Self::Memory(input) => input.seek(pos),
+        }
+    }
+}
+
+fn bits_of(img: Pnm) -> Vec<bool> {
+    match img {
+        Pnm::Bits(img) => img.pixels,
+        img => img.gray().pixels.into_iter().map(|v| v >= 128).collect(),
+    }
+}
+
+fn gray_of(img: Pnm) -> Vec<u8> {
+    img.gray().pixels
+}
+
+/// pixel stream of the clip to play with what its header tells about it
+pub struct Clip {
+    pub pixels: Pixels,
+    pub size: Size,
+    pub fps: Option<u64>,
+    /// a single image that stays on screen
+    pub still: bool,
+    /// how long each frame stays, from the start of the clip, frames past the end or
+    /// without a delay follow the frame rate
+    pub delays: Vec<Option<Duration>>,
+}
+
+impl Clip {
+    fn new(pixels: Pixels, size: Size) -> Self {
+        Self {
+            pixels,
+            size,
+            fps: None,
+            still: false,
+            delays: Vec::new(),
+        }
+    }
+
+    /// raw `monob` pixels of the given size
+    fn raw(mut input: Input, size: Size) -> Self {
+        let Some(len) = input.byte_len() else {
+            return Self::new(
+                Pixels::Bits(Box::new(Stream::new(raw_frames(input, size)))),
+                size,
+            );
+        };
+
+        let frame_len = size.w.div_ceil(8) * size.h;
+        let frames = len as usize / frame_len.max(1);
+        let mut at = 0;
+        let pixels = Images::new(Some(frames), move |i| {
+            if i >= frames {
+                return Ok(None);
+            }
+            if at != i {
+                input.seek(SeekFrom::Start((i * frame_len) as u64))?;
+            }
+            let mut frame = vec![0u8; frame_len];
+            input.read_exact(&mut frame)?;
+            at = i + 1;
+            Ok(Some(Bits::new(&frame[..], size.w).collect()))
+        });
+        Self::new(Pixels::Bits(Box::new(pixels)), size)
+    }
+
+    /// Netpbm images read one after another, 1-bit for bitmaps, grayscale otherwise,
+    /// the first image sets the size and the clip ends before an image of another size
+    fn pnm_stream<F>(first: Pnm, mut next: F) -> io::Result<Self>
+    where
+        F: FnMut() -> io::Result<Option<Pnm>> + Send + 'static,
+    {
+        let second = next()?;
+        let still = second.is_none();
+        let size = first.size();
+        let bits = matches!(first, Pnm::Bits(_));
+
+        let mut read = iter::once(first).chain(second);
+        let mut image = move || -> io::Result<Option<Pnm>> {
+            let img = match read.next() {
+                Some(img) => Some(img),
+                None => next()?,
+            };
+            Ok(img.filter(|img| img.size() == size))
+        };
+        let pixels = if bits {
+            Pixels::Bits(Box::new(Stream::new(move || Ok(image()?.map(bits_of)))))
+        } else {
+            Pixels::Gray(Box::new(Stream::new(move || Ok(image()?.map(gray_of)))))
+        };
+
+        Ok(Self {
+            still,
+            ..Self::new(pixels, size)
+        })
+    }
+
+    /// numbered images matching a file name pattern with one `*`, in the order of
+    /// their numbers, typed and sized like the first one
+    fn sequence(pattern: &str) -> io::Result<Self> {
+        let paths = numbered(pattern)?;
+        let load = |path: &Path| {
+            let mut input = BufReader::new(File::open(path)?);
+            pnm::read(&mut input)?.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("no image in {}", path.display()),
+                )
+            })
+        };
+        let first = match paths.first() {
+            Some(path) => load(path)?,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("no image matches {}", pattern),
+                ));
+            }
+        };
+
+        let size = first.size();
+        let frames = Some(paths.len());
+        let still = paths.len() == 1;
+        // an image of another size ends the clip like the end of a stream does
+        let image = move |i: usize| -> io::Result<Option<Pnm>> {
+            match paths.get(i) {
+                Some(path) => Ok(Some(load(path)?).filter(|img| img.size() == size)),
+                None => Ok(None),
+            }
+        };
+        let pixels = match first {
+            Pnm::Bits(_) => Pixels::Bits(Box::new(Images::new(frames, move |i| {
+                Ok(image(i)?.map(bits_of))
+            }))),
+            _ => Pixels::Gray(Box::new(Images::new(frames, move |i| {
+                Ok(image(i)?.map(gray_of))
+            }))),
+        };
+
+        Ok(Self {
+            still,
+            ..Self::new(pixels, size)
+        })
+    }
+
+    /// GIF animations are short, they are decoded up front to know their delays
+    fn gif(input: Input) -> io::Result<Self> {
+        let mut decoder = gif::Decoder::new(input)?;
+        let size = decoder.size();
+        let (mut frames, mut delays): (Vec<Vec<u8>>, Vec<_>) = (Vec::new(), Vec::new());
+        // the frames before a broken one still play, the error comes after them
+        let mut error = None;
+        loop {
+            match decoder.next_frame() {
+                Ok(Some(f)) => {
+                    frames.push(f.image.pixels.into_iter().map(luma).collect());
+                    delays.push(f.delay);
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    error = Some(e);
+                    break;
+                }
+            }
+        }
+        if frames.is_empty() {
+            return Err(error.unwrap_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "gif has no frames")
+            }));
+        }
+
+        let still = frames.len() == 1 && error.is_none();
+        let pixels = Images::new(Some(frames.len()), move |i| match frames.get(i) {
+            Some(frame) => Ok(Some(frame.clone())),
+            None => error.take().map_or(Ok(None), Err),
+        });
+        Ok(Self {
+            still,
+            delays,
+            ..Self::new(Pixels::Gray(Box::new(pixels)), size)
+        })
+    }
+
+    fn y4m(input: Input) -> io::Result<Self> {
+        let len = input.byte_len();
+        let reader = y4m::Reader::new(input)?;
+        let header = *reader.header();
+
+        let pixels: Box<dyn Source<Item = u8>> = match len {
+            Some(len) => {
+                let frames = reader.frames(len);
+                let mut reader = reader;
+                Box::new(Images::new(Some(frames), move |i| {
+                    if reader.position() != i {
+                        reader.seek(i)?;
+                    }
+                    Ok(reader.next_frame()?.map(|f| f.pixels))
+                }))
+            }
+            None => {
+                let mut reader = reader;
+                Box::new(Stream::new(move || {
+                    Ok(reader.next_frame()?.map(|f| f.pixels))
+                }))
+            }
+        };
+        Ok(Self {
+            fps: header.fps(),
+            ..Self::new(Pixels::Gray(pixels), header.size)
+        })
+    }
+
+    fn bapl(input: Input) -> io::Result<Self> {
+        let seekable = input.byte_len().is_some();
+        let reader = container::Reader::new(input)?;
+        let header = *reader.header();
+
+        let pixels: Box<dyn Source<Item = bool>> = if seekable {
+            let mut reader = reader;
+            Box::new(Images::new(Some(header.frames as usize), move |i| {
+                if reader.position() as usize != i {
+                    reader.seek(i as u32)?;
+                }
+                Ok(reader.next_frame()?.map(|f| f.pixels))
+            }))
+        } else {
+            let mut reader = reader;
+            Box::new(Stream::new(move || {
+                Ok(reader.next_frame()?.map(|f| f.pixels))
+            }))
+        };
+        Ok(Self {
+            fps: Some(header.fps as u64).filter(|&fps| fps > 0),
+            ..Self::new(Pixels::Bits(pixels), header.size)
+        })
+    }
+
+    /// a `.bapl` clip, a Y4M stream, a GIF or Netpbm images if the input starts like one,
+    /// raw `monob` pixels of `size` otherwise
+    fn detect(mut input: Input, size: Size) -> io::Result<Self> {
+        let head = input.fill_buf()?;
+        if let [b'P', b'1'..=b'6', space, ..] = head
+            && space.is_ascii_whitespace()
+        {
+            let first = pnm::read(&mut input)?
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no image found"))?;
+            return Self::pnm_stream(first, move || pnm::read(&mut input));
+        }
+
+        if head.starts_with(y4m::MAGIC) {
+            Self::y4m(input)
+        } else if head.starts_with(b"GIF8") {
+            Self::gif(input)
+        } else if head.starts_with(container::MAGIC) {
+            Self::bapl(input)
+        } else {
+            Ok(Self::raw(input, size))
+        }
+    }
+}
+
+/// files of the directory named like `pattern`, a `*` in the file name stands
+/// for the frame number
+fn numbered(pattern: &str) -> io::Result<Vec<PathBuf>> {
+    let pattern = Path::new(pattern);
+    let dir = match pattern.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = pattern.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let Some((prefix, suffix)) = name.split_once('*') else {
+        return Ok(vec![pattern.to_path_buf()]);
+    };
+
+    let mut numbered = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        // files where the `*` is not a number are not frames
+        if name.len() >= prefix.len() + suffix.len()
+            && let Some(num) = name
+                .strip_prefix(prefix)
+                .and_then(|n| n.strip_suffix(suffix))
+            && let Ok(num) = num.parse::<u64>()
+        {
+            numbered.push((num, name.to_string(), path));
+        }
+    }
+    numbered.sort();
+    Ok(numbered.into_iter().map(|(_, _, path)| path).collect())
+}
+
+/// opens a file, an image sequence when it has a `*`, stdin for `-` or the clip
+/// built into the binary, `size` only applies to raw input
+pub fn open(input: Option<&str>, size: Size) -> io::Result<Clip> {
+    match input {
+        Some("-") => Clip::detect(Input::Stdin(BufReader::new(io::stdin())), size),
+        Some(pattern) if pattern.contains('*') => Clip::sequence(pattern),
+        Some(path) => Clip::detect(Input::File(BufReader::new(File::open(path)?)), size),
+        None => embedded(size),
+    }
+}
+
+/// the terminal in raw mode until dropped, one for everything that reads keys,
+/// the controlling terminal when stdin carries the clip
+pub struct Keyboard(TtyWithGuard<File>);
+
+impl Keyboard {
+    pub fn open() -> io::Result<Self> {
+        let file = if io::stdin().is_terminal() {
+            File::from(io::stdin().as_fd().try_clone_to_owned()?)
+        } else {
+            File::open("/dev/tty")?
+        };
+        let mut tty = file.guard_mode()?;
+        tty.modify_mode(raw_timed)?;
+        Ok(Self(tty))
+    }
+
+    /// another handle to read keys with, e.g. on another thread
+    pub fn reader(&self) -> io::Result<KeyReader> {
+        Ok(KeyReader(self.0.try_clone()?))
+    }
+}
+
+/// reads of the raw terminal, they give up after a tenth of a second with
+/// `WouldBlock` so nothing is stuck reading when no key comes
+pub struct KeyReader(File);
+
+impl Read for KeyReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0.read(buf)? {
+            // no key within `VTIME`, a terminal has no end
+            0 if !buf.is_empty() => Err(io::ErrorKind::WouldBlock.into()),
+            n => Ok(n),
+        }
+    }
+}
+
+/// what `cfmakeraw` sets, with reads returning empty after `VTIME` tenths
+fn raw_timed(mut ios: Termios) -> Termios {
+    ios.c_iflag &= !(libc::IGNBRK
+        | libc::BRKINT
+        | libc::PARMRK
+        | libc::ISTRIP
+        | libc::INLCR
+        | libc::IGNCR
+        | libc::ICRNL
+        | libc::IXON);
+    ios.c_oflag &= !libc::OPOST;
+    ios.c_lflag &= !(libc::ECHO | libc::ECHONL | libc::ICANON | libc::ISIG | libc::IEXTEN);
+    ios.c_cflag &= !(libc::CSIZE | libc::PARENB);
+    ios.c_cflag |= libc::CS8;
+    ios.c_cc[libc::VMIN] = 0;
+    ios.c_cc[libc::VTIME] = 1;
+    ios
+}
+
+#[cfg(feature = "embedded")]
+fn embedded(size: Size) -> io::Result<Clip> {
+    const APPLE: &[u8] = include_bytes!("../assets/apple480.raw");
+    Ok(Clip::raw(Input::Memory(Cursor::new(APPLE)), size))
+}
+
+#[cfg(not(feature = "embedded"))]
+fn embedded(_size: Size) -> io::Result<Clip> {
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "no clip given, pass --input <path> or build with the `embedded` feature",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, io::Cursor, process, time::Duration};
+
+    use renderer::{scale::Resample, types::Size};
+
+    use super::{Bits, Clip, Input, Pixels, Stream, numbered, raw_frames};
+    use crate::{
+        container::Writer,
+        frame::{Timed, ToFrames},
+    };
+
+    #[test]
+    fn rows_skip_padding() {
+        // 3 pixel wide rows, each padded to a byte
+        let data: &[u8] = &[0b1010_0000, 0b0111_1111, 0b1100_0000];
+        let bits: Vec<bool> = Bits::new(data, 3).collect();
+        assert_eq!(
+            bits,
+            [true, false, true, false, true, true, true, true, false]
+        );
+
+        let bits: Vec<bool> = Bits::new(&[0xF0u8][..], 8).collect();
+        assert_eq!(bits.iter().filter(|b| **b).count(), 4);
+    }
+
+    #[test]
+    fn sequences_in_number_order() {
+        let dir = env::temp_dir().join(format!("bad-apple-seq-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (name, v) in [
+            ("f10.pgm", 3u8),
+            ("f2.pgm", 2),
+            ("f1.pgm", 1),
+            ("f.txt", 0),
+            ("f_old.pgm", 0),
+            ("f.pgm", 0),
+        ] {
+            fs::write(
+                dir.join(name),
+                [b"P5 2 1 255\n".as_slice(), &[v, v]].concat(),
+            )
+            .unwrap();
+        }
+        let pattern = dir.join("f*.pgm");
+        let pattern = pattern.to_str().unwrap();
+
+        let names: Vec<_> = numbered(pattern)
+            .unwrap()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_owned())
+            .collect();
+        assert_eq!(names, ["f1.pgm", "f2.pgm", "f10.pgm"]);
+
+        let clip = Clip::sequence(pattern).unwrap();
+        assert!(!clip.still);
+        let Pixels::Gray(pixels) = clip.pixels else {
+            panic!("gray images give gray pixels");
+        };
+        assert_eq!(pixels.collect::<Vec<_>>(), [1, 1, 2, 2, 3, 3]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn gif_delays_follow_frames() {
+        let data: &'static [u8] = include_bytes!("../../renderer/fixtures/anim.gif");
+        let clip = Clip::detect(Input::Memory(Cursor::new(data)), Size::new(1, 1)).unwrap();
+        assert_eq!((clip.size.w, clip.size.h), (6, 4));
+        assert!(!clip.still);
+
+        let ms = |ms| Some(Duration::from_millis(ms));
+        assert_eq!(clip.delays, [ms(100), ms(200), ms(50), None]);
+        let Pixels::Gray(pixels) = clip.pixels else {
+            panic!("gifs decode to gray");
+        };
+        assert_eq!(pixels.count(), 4 * 24);
+    }
+
+    #[test]
+    fn raw_frames_seek() {
+        // three 8x1 frames
+        let data: &'static [u8] = &[0x00, 0xFF, 0x0F];
+        let size = Size::new(8, 1);
+        let clip = Clip::raw(Input::Memory(Cursor::new(data)), size);
+        assert_eq!(clip.pixels.frames(), Some(3));
+        let Pixels::Bits(pixels) = clip.pixels else {
+            panic!("raw clips are 1-bit");
+        };
+        let lit = |t: Timed| (t.index, t.frame.pixels.iter().filter(|&&b| b).count());
+
+        let mut frames = pixels.to_frames(size, size, Resample::Nearest);
+        assert!(frames.seek(2));
+        assert_eq!(frames.next_timed().map(lit), Some((2, 4)));
+        assert!(frames.seek(0));
+        assert_eq!(frames.next_timed().map(lit), Some((0, 0)));
+
+        // streams only skip ahead
+        let mut frames =
+            Stream::new(raw_frames(data, size)).to_frames(size, size, Resample::Nearest);
+        assert!(frames.seek(1));
+        assert!(!frames.seek(0));
+        assert_eq!(frames.next_timed().map(lit), Some((1, 8)));
+    }
+
+    #[test]
+    fn broken_clips_end_with_their_error() {
+        // a stream of 2x1 images, the third is cut off
+        let data: &'static [u8] = b"P5 2 1 255\n\x01\x01P5 2 1 255\n\x02\x02P5 2 1 255\n\x03";
+        let clip = Clip::detect(Input::Memory(Cursor::new(data)), Size::new(1, 1)).unwrap();
+        let Pixels::Gray(pixels) = clip.pixels else {
+            panic!("gray images give gray pixels");
+        };
+        let mut frames = pixels.to_frames(clip.size, clip.size, Resample::Nearest);
+        assert_eq!(frames.by_ref().count(), 2);
+        assert!(frames.error().is_some());
+
+        // a seekable clip with its last record cut short
+        let size = Size::new(8, 1);
+        let mut writer = Writer::new(Cursor::new(Vec::new()), size, 30).unwrap();
+        for frame in Stream::new(raw_frames(&[0x0F, 0xF0, 0xFF][..], size)).to_frames(
+            size,
+            size,
+            Resample::Nearest,
+        ) {
+            writer.push(&frame).unwrap();
+        }
+        // the index after the records goes too, but it is only needed to seek
+        let mut data = writer.finish().unwrap().into_inner();
+        let index_at = u64::from_le_bytes(data[19..27].try_into().unwrap());
+        data.truncate(index_at as usize - 1);
+        let data: &'static [u8] = data.leak();
+        let clip = Clip::detect(Input::Memory(Cursor::new(data)), size).unwrap();
+        let Pixels::Bits(pixels) = clip.pixels else {
+            panic!("bapl clips are 1-bit");
+        };
+        let mut frames = pixels.to_frames(size, size, Resample::Nearest);
+        assert_eq!(frames.by_ref().count(), 2);
+        assert!(frames.error().is_some());
+        assert!(frames.error().is_none());
+    }
+}
@@ -0,0 +1,207 @@
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+
+use renderer::{image::Image, types::Size};
+
+pub const MAGIC: &[u8] = b"YUV4MPEG2 ";
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Header {
+    pub size: Size,
+    /// frame rate as numerator and denominator
+    pub rate: Option<(u64, u64)>,
+    /// bytes of chroma and alpha following the luma plane of every frame
+    pub extra: usize,
+}
+
+impl Header {
+    /// parses the stream header line without its newline
+    fn parse(line: &str) -> io::Result<Self> {
+        let mut w = None;
+        let mut h = None;
+        let mut rate = None;
+        let mut colorspace = "420jpeg";
+
+        for param in line.split(' ').skip(1).filter(|p| !p.is_empty()) {
+            // unknown parameters may start with any character
+            let Some((tag, val)) = param.split_at_checked(1) else {
+                continue;
+            };
+            match tag {
+                "W" => w = val.parse().ok(),
+                "H" => h = val.parse().ok(),
+                "F" => {
+                    rate = val
+                        .split_once(':')
+                        .and_then(|(n, d)| Some((n.parse().ok()?, d.parse().ok()?)))
+                        .filter(|&(n, d)| n > 0 && d > 0)
+                }
+                "C" => colorspace = val,
+                _ => {}
+            }
+        }
+
+        let (Some(w), Some(h)) = (w, h) else {
+            return Err(invalid("y4m header lacks W or H".into()));
+        };
+        let size = Size::new(w, h);
+        let chroma = size.w.div_ceil(2) * size.h.div_ceil(2);
+        let extra = match colorspace {
+            "mono" => 0,
+            "420" | "420jpeg" | "420paldv" | "420mpeg2" => 2 * chroma,
+            "422" => 2 * size.w.div_ceil(2) * size.h,
+            "444" => 2 * size.flatten(),
+            "444alpha" => 3 * size.flatten(),
+            c => return Err(invalid(format!("unsupported y4m colorspace {}", c))),
+        };
+
+        Ok(Self { size, rate, extra })
+    }
+
+    pub fn fps(&self) -> Option<u64> {
+        self.rate
+            .map(|(n, d)| (n as f64 / d as f64).round().max(1.0) as u64)
+    }
+}
+
+/// YUV4MPEG2 stream, every frame is its luma plane
+pub struct Reader<R: BufRead> {
+    input: R,
+    header: Header,
+    /// length of the stream header
+    start: u64,
+    next: usize,
+}
+
+impl<R: BufRead> Reader<R> {
+    pub fn new(mut input: R) -> io::Result<Self> {
+        let mut line = String::new();
+        input.read_line(&mut line)?;
+        if !line.as_bytes().starts_with(MAGIC) {
+            return Err(invalid("not a y4m stream".into()));
+        }
+
+        let header = Header::parse(line.trim_end_matches('\n'))?;
+        if header.size.flatten() == 0 {
+            return Err(invalid("y4m stream has no pixels".into()));
+        }
+        Ok(Self {
+            input,
+            header,
+            start: line.len() as u64,
+            next: 0,
+        })
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// index of the frame `next_frame` returns
+    pub fn position(&self) -> usize {
+        self.next
+    }
+
+    /// bytes of a frame with a bare `FRAME` marker, as ffmpeg writes them
+    fn frame_len(&self) -> u64 {
+        (b"FRAME\n".len() + self.header.size.flatten() + self.header.extra) as u64
+    }
+
+    /// frames in a stream of `len` bytes
+    pub fn frames(&self, len: u64) -> usize {
+        (len.saturating_sub(self.start) / self.frame_len()) as usize
+    }
+
+    /// `None` at the end of the stream, a partial frame is an error
+    pub fn next_frame(&mut self) -> io::Result<Option<Image<u8>>> {
+        let mut line = Vec::new();
+        if self.input.read_until(b'\n', &mut line)? == 0 {
+            return Ok(None);
+        }
+        if !line.starts_with(b"FRAME") {
+            return Err(invalid("y4m frame marker missing".into()));
+        }
+
+        let mut luma = vec![0u8; self.header.size.flatten()];
+        self.input.read_exact(&mut luma)?;
+        io::copy(
+            &mut self.input.by_ref().take(self.header.extra as u64),
+            &mut io::sink(),
+        )?;
+
+        self.next += 1;
+        Ok(Some(Image::new(self.header.size, luma)))
+    }
+}
+
+impl<R: BufRead + Seek> Reader<R> {
+    /// positions the reader so `next_frame` returns frame `n`, frame markers with
+    /// parameters throw it off
+    pub fn seek(&mut self, n: usize) -> io::Result<()> {
+        self.input
+            .seek(SeekFrom::Start(self.start + n as u64 * self.frame_len()))?;
+        self.next = n;
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+    type Item = io::Result<Image<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_frame().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::{Header, Reader};
+
+    #[test]
+    fn header_params() {
+        let h =
+            Header::parse("YUV4MPEG2 W5 H3 F30000:1001 Ip A1:1 C420jpeg XYSCSS=420JPEG").unwrap();
+        assert_eq!((h.size.w, h.size.h), (5, 3));
+        assert_eq!(h.fps(), Some(30));
+        assert_eq!(h.extra, 2 * 3 * 2);
+
+        let h = Header::parse("YUV4MPEG2 H2 W4 Cmono é Xé").unwrap();
+        assert_eq!((h.extra, h.fps()), (0, None));
+
+        assert!(Header::parse("YUV4MPEG2 W4 F25:1").is_err());
+        assert!(Header::parse("YUV4MPEG2 W4 H4 C420p10").is_err());
+    }
+
+    #[test]
+    fn luma_frames() {
+        let mut data = b"YUV4MPEG2 W3 H2 F25:1 C420\n".to_vec();
+        for f in 0..2u8 {
+            data.extend(b"FRAME\n");
+            data.extend([f; 6]);
+            // two 2x1 chroma planes
+            data.extend([128; 4]);
+        }
+        data.extend(b"FRAME\n\x01\x02");
+
+        let mut reader = Reader::new(&data[..]).unwrap();
+        assert_eq!(reader.header().fps(), Some(25));
+        assert_eq!(reader.next_frame().unwrap().unwrap().pixels, [0; 6]);
+        assert_eq!(reader.next_frame().unwrap().unwrap().pixels, [1; 6]);
+        assert!(reader.next_frame().is_err());
+
+        let frames: Vec<_> = Reader::new(&data[..]).unwrap().collect();
+        assert_eq!(frames.len(), 3);
+        assert!(frames[2].is_err());
+
+        let mut reader = Reader::new(Cursor::new(&data)).unwrap();
+        assert_eq!(reader.frames(data.len() as u64), 2);
+        reader.seek(1).unwrap();
+        assert_eq!(reader.next_frame().unwrap().unwrap().pixels, [1; 6]);
+        assert_eq!(reader.position(), 2);
+    }
+}
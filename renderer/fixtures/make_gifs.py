@@ -0,0 +1,145 @@
+#!/usr/bin/env python3
+"""Writes the GIF fixtures used by the tests of `renderer::gif`.
+
+Run from this directory, the files are committed so the tests need no python.
+"""
+
+import struct
+
+
+def lzw(indices, min_size):
+    clear, eoi = 1 << min_size, (1 << min_size) + 1
+    size, next_code = min_size + 1, eoi + 1
+    table = {(i,): i for i in range(clear)}
+    acc = bits = 0
+    out = bytearray()
+
+    def emit(code):
+        nonlocal acc, bits, size
+        acc |= code << bits
+        bits += size
+        while bits >= 8:
+            out.append(acc & 0xFF)
+            acc >>= 8
+            bits -= 8
+        # the decoder adds its entry one code later, so it grows right here
+        if next_code >= 1 << size and size < 12:
+            size += 1
+
+    emit(clear)
+    word = ()
+    for k in indices:
+        if word + (k,) in table:
+            word += (k,)
+            continue
+        emit(table[word])
+        if next_code < 4096:
+            table[word + (k,)] = next_code
+            next_code += 1
+        else:
+            # full dictionary, start over
+            emit(clear)
+            table = {(i,): i for i in range(clear)}
+            size, next_code = min_size + 1, eoi + 1
+        word = (k,)
+    emit(table[word])
+    emit(eoi)
+    if bits:
+        out.append(acc & 0xFF)
+    return bytes(out)
+
+
+def blocks(data):
+    out = bytearray()
+    for i in range(0, len(data), 255):
+        chunk = data[i : i + 255]
+        out += bytes([len(chunk)]) + chunk
+    return bytes(out) + b"\0"
+
+
+def table(colors):
+    return b"".join(bytes(c) for c in colors)
+
+
+def table_bits(colors):
+    return max(len(colors) - 1, 1).bit_length() - 1
+
+
+def control(disposal, delay, transparent=None):
+    packed = disposal << 2 | (transparent is not None)
+    return b"\x21\xf9\x04" + struct.pack("<BHB", packed, delay, transparent or 0) + b"\0"
+
+
+def image(x, y, w, h, indices, local=None, interlace=False, min_size=2):
+    packed = (interlace << 6) | ((0x80 | table_bits(local)) if local else 0)
+    out = b"\x2c" + struct.pack("<HHHHB", x, y, w, h, packed)
+    if local:
+        out += table(local)
+    return out + bytes([min_size]) + blocks(lzw(indices, min_size))
+
+
+def gif(version, w, h, colors, body, background=0):
+    packed = 0x80 | table_bits(colors) << 4 | table_bits(colors)
+    head = version + struct.pack("<HHBBB", w, h, packed, background, 0)
+    return head + table(colors) + body + b"\x3b"
+
+
+BLACK, WHITE, RED, BLUE, GREEN = (0, 0, 0), (255, 255, 255), (255, 0, 0), (0, 0, 255), (0, 255, 0)
+
+# 4x4 checkerboard, no extensions at all
+still = gif(
+    b"GIF87a",
+    4,
+    4,
+    [BLACK, WHITE],
+    image(0, 0, 4, 4, [(x + y) % 2 for y in range(4) for x in range(4)]),
+)
+
+# 6x4 canvas, looping forever:
+# 0. white everywhere, kept
+# 1. green diagonal at (1, 1) through a local palette with a transparent index,
+#    restored to the background afterwards
+# 2. red square at (3, 1), restored to what was there before
+# 3. interlaced 2x4 column at (4, 0), rows red, blue, black, red, no delay
+anim = gif(
+    b"GIF89a",
+    6,
+    4,
+    [BLACK, WHITE, RED, BLUE],
+    b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00"
+    + control(1, 10)
+    + image(0, 0, 6, 4, [1] * 24)
+    + control(2, 20, transparent=0)
+    + image(1, 1, 2, 2, [1, 0, 0, 1], local=[BLACK, GREEN])
+    + control(3, 5)
+    + image(3, 1, 2, 2, [2] * 4)
+    + control(0, 0)
+    + image(4, 0, 2, 4, [2, 2, 0, 0, 3, 3, 2, 2], interlace=True),
+)
+
+
+def noise(w, h):
+    """gray levels of an LCG with black gaps, `gif::tests` computes the same"""
+    v, out = 1, []
+    for y in range(h):
+        for x in range(w):
+            v = (v * 1103515245 + 12345) & 0x7FFFFFFF
+            out.append((v >> 16) & 15 if (x // 8 + y) % 7 else 0)
+    return out
+
+
+# 128x96 of 16 grays, enough codes to fill the dictionary
+grays = gif(
+    b"GIF89a",
+    128,
+    96,
+    [(i * 17,) * 3 for i in range(16)],
+    image(0, 0, 128, 96, noise(128, 96), min_size=4),
+)
+
+with open("still.gif", "wb") as f:
+    f.write(still)
+with open("anim.gif", "wb") as f:
+    f.write(anim)
+with open("noise.gif", "wb") as f:
+    f.write(grays)
@@ -0,0 +1,489 @@
+use std::{f32::consts::PI, time::Duration};
+
+use crate::{image::Image, types::Color};
+
+/// maps linear progress in `[0, 1]` onto a curve
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineInOut,
+    BounceOut,
+    /// jumps to the end value at the very end
+    Step,
+}
+
+impl Easing {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::QuadIn => t * t,
+            Self::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Self::QuadInOut if t < 0.5 => 2.0 * t * t,
+            Self::QuadInOut => 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0,
+            Self::CubicIn => t * t * t,
+            Self::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Self::CubicInOut if t < 0.5 => 4.0 * t * t * t,
+            Self::CubicInOut => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+            Self::SineInOut => -((PI * t).cos() - 1.0) / 2.0,
+            Self::BounceOut => bounce_out(t),
+            Self::Step => (t >= 1.0) as u8 as f32,
+        }
+    }
+}
+
+fn bounce_out(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+/// values that can be interpolated, `t` may leave `[0, 1]` for overshooting curves
+pub trait Lerp {
+    fn lerp(&self, to: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for f64 {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t as f64
+    }
+}
+
+impl Lerp for usize {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        (*self as f32).lerp(&(*to as f32), t).round().max(0.0) as usize
+    }
+}
+
+impl Lerp for u8 {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        (*self as f32)
+            .lerp(&(*to as f32), t)
+            .round()
+            .clamp(0.0, 255.0) as u8
+    }
+}
+
+impl<A: Lerp, B: Lerp> Lerp for (A, B) {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        (self.0.lerp(&to.0, t), self.1.lerp(&to.1, t))
+    }
+}
+
+/// blends in rgb, the result is always `Color::Rgb`
+impl Lerp for Color {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        let (r0, g0, b0) = self.to_rgb();
+        let (r1, g1, b1) = to.to_rgb();
+        Color::Rgb(r0.lerp(&r1, t), g0.lerp(&g1, t), b0.lerp(&b1, t))
+    }
+}
+
+/// something that progresses with time and can be sampled
+pub trait Animation {
+    type Value;
+
+    fn advance(&mut self, dt: Duration);
+    fn value(&self) -> Self::Value;
+    fn is_done(&self) -> bool;
+}
+
+/// single transition between two values
+#[derive(Debug, Clone)]
+pub struct Tween<T> {
+    from: T,
+    to: T,
+    duration: Duration,
+    easing: Easing,
+    elapsed: Duration,
+}
+
+impl<T: Lerp + Clone> Tween<T> {
+    pub fn new(from: T, to: T, duration: Duration) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            easing: Easing::Linear,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// linear progress in `[0, 1]`, before easing
+    pub fn progress(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        (self.elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+    }
+}
+
+impl<T: Lerp + Clone> Animation for Tween<T> {
+    type Value = T;
+
+    fn advance(&mut self, dt: Duration) {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+    }
+
+    fn value(&self) -> T {
+        self.from.lerp(&self.to, self.easing.apply(self.progress()))
+    }
+
+    fn is_done(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+/// chain of tweens, each starting where the previous one ended
+#[derive(Debug, Clone)]
+pub struct Keyframes<T> {
+    start: T,
+    frames: Vec<(T, Duration, Easing)>,
+    looped: bool,
+    elapsed: Duration,
+}
+
+impl<T: Lerp + Clone> Keyframes<T> {
+    pub fn new(start: T) -> Self {
+        Self {
+            start,
+            frames: Vec::new(),
+            looped: false,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// moves to `value` over `duration`
+    pub fn then(mut self, value: T, duration: Duration, easing: Easing) -> Self {
+        self.frames.push((value, duration, easing));
+        self
+    }
+
+    /// holds the current value for `duration`
+    pub fn hold(self, duration: Duration) -> Self {
+        let last = self.frames.last().map_or(&self.start, |f| &f.0).clone();
+        self.then(last, duration, Easing::Step)
+    }
+
+    /// starts over instead of finishing, the last value should match the first
+    pub fn looped(mut self) -> Self {
+        self.looped = true;
+        self
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.frames.iter().map(|f| f.1).sum()
+    }
+}
+
+impl<T: Lerp + Clone> Animation for Keyframes<T> {
+    type Value = T;
+
+    fn advance(&mut self, dt: Duration) {
+        let total = self.duration();
+        self.elapsed += dt;
+        if self.elapsed >= total {
+            self.elapsed = if self.looped && !total.is_zero() {
+                Duration::from_nanos((self.elapsed.as_nanos() % total.as_nanos()) as u64)
+            } else {
+                total
+            };
+        }
+    }
+
+    fn value(&self) -> T {
+        let mut from = &self.start;
+        let mut left = self.elapsed;
+        for (to, duration, easing) in &self.frames {
+            if left < *duration {
+                let t = left.as_secs_f32() / duration.as_secs_f32();
+                return from.lerp(to, easing.apply(t));
+            }
+            left -= *duration;
+            from = to;
+        }
+        from.clone()
+    }
+
+    fn is_done(&self) -> bool {
+        !self.looped && self.elapsed >= self.duration()
+    }
+}
+
+/// handle to an animation started on a `Scheduler`
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct AnimId(usize);
+
+struct Entry<T> {
+    id: AnimId,
+    delay: Duration,
+    anim: Box<dyn Animation<Value = T>>,
+}
+
+/// runs animations of the same value type, ticked once per frame by the frame loop
+pub struct Scheduler<T> {
+    entries: Vec<Entry<T>>,
+    next_id: usize,
+}
+
+impl<T> Default for Scheduler<T> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            next_id: 0,
+        }
+    }
+}
+
+impl<T> Scheduler<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn start<A>(&mut self, anim: A) -> AnimId
+    where
+        A: Animation<Value = T> + 'static,
+    {
+        self.start_after(Duration::ZERO, anim)
+    }
+
+    /// the animation holds its first value until `delay` passed
+    pub fn start_after<A>(&mut self, delay: Duration, anim: A) -> AnimId
+    where
+        A: Animation<Value = T> + 'static,
+    {
+        let id = AnimId(self.next_id);
+        self.next_id += 1;
+        self.entries.push(Entry {
+            id,
+            delay,
+            anim: Box::new(anim),
+        });
+        id
+    }
+
+    /// advances everything by `dt`, returns the animations that finished during this tick,
+    /// they keep their final value until removed
+    pub fn tick(&mut self, dt: Duration) -> Vec<AnimId> {
+        let mut finished = Vec::new();
+        for entry in &mut self.entries {
+            if entry.anim.is_done() {
+                continue;
+            }
+
+            let waited = dt.min(entry.delay);
+            entry.delay -= waited;
+            entry.anim.advance(dt - waited);
+            if entry.anim.is_done() {
+                finished.push(entry.id);
+            }
+        }
+        finished
+    }
+
+    pub fn value(&self, id: AnimId) -> Option<T> {
+        self.entries
+            .iter()
+            .find(|e| e.id == id)
+            .map(|e| e.anim.value())
+    }
+
+    pub fn is_running(&self, id: AnimId) -> bool {
+        self.entries.iter().any(|e| e.id == id && !e.anim.is_done())
+    }
+
+    /// true once every animation finished
+    pub fn is_idle(&self) -> bool {
+        self.entries.iter().all(|e| e.anim.is_done())
+    }
+
+    pub fn remove(&mut self, id: AnimId) {
+        self.entries.retain(|e| e.id != id);
+    }
+}
+
+/// how one frame replaces another
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Transition {
+    /// the new frame slides in from the left edge
+    #[default]
+    WipeRight,
+    /// the new frame slides in from the top edge
+    WipeDown,
+    /// pixels switch over in a fixed pseudo random order
+    Dissolve,
+    /// pixels switch over along an ordered dither pattern, reads as a fade on 1 bit frames
+    Fade,
+}
+
+const BAYER_4: [u8; 16] = [0, 8, 2, 10, 12, 4, 14, 6, 3, 11, 1, 9, 15, 7, 13, 5];
+
+impl Transition {
+    /// mixes two frames of the same size, `t` runs from 0 (all `from`) to 1 (all `to`)
+    pub fn blend<T: Copy>(&self, from: &Image<T>, to: &Image<T>, t: f32) -> Image<T> {
+        assert_eq!(
+            from.size, to.size,
+            "transition between frames of different size"
+        );
+
+        let t = t.clamp(0.0, 1.0);
+        let w = from.size.w;
+        let h = from.size.h;
+        let pixels = (0..h)
+            .flat_map(|y| (0..w).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let switched = match self {
+                    Self::WipeRight => (x as f32) < t * w as f32,
+                    Self::WipeDown => (y as f32) < t * h as f32,
+                    Self::Dissolve => (hash(x, y) as f32 + 0.5) / 256.0 < t,
+                    Self::Fade => (BAYER_4[(y % 4) * 4 + x % 4] as f32 + 0.5) / 16.0 < t,
+                };
+                if switched {
+                    *to.get(x, y)
+                } else {
+                    *from.get(x, y)
+                }
+            })
+            .collect();
+        Image::new(from.size, pixels)
+    }
+}
+
+/// cheap stable per pixel noise
+fn hash(x: usize, y: usize) -> u8 {
+    let mut v = (x as u32).wrapping_mul(0x9E37_79B1) ^ (y as u32).wrapping_mul(0x85EB_CA77);
+    v ^= v >> 15;
+    v = v.wrapping_mul(0x2C1B_3C6D);
+    v ^= v >> 12;
+    (v >> 24) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Animation, Easing, Keyframes, Lerp, Scheduler, Transition, Tween};
+    use crate::{
+        image::Image,
+        types::{Color, Size},
+    };
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn easing_ends() {
+        for e in [
+            Easing::Linear,
+            Easing::QuadInOut,
+            Easing::CubicOut,
+            Easing::SineInOut,
+            Easing::BounceOut,
+            Easing::Step,
+        ] {
+            assert!(e.apply(0.0).abs() < 1e-5, "{:?}", e);
+            assert!((e.apply(1.0) - 1.0).abs() < 1e-5, "{:?}", e);
+        }
+        assert!(Easing::QuadIn.apply(0.5) < 0.5 && Easing::QuadOut.apply(0.5) > 0.5);
+    }
+
+    #[test]
+    fn tween_values() {
+        let mut tw = Tween::new((0usize, 10usize), (10, 0), 100 * MS);
+        tw.advance(50 * MS);
+        assert_eq!(tw.value(), (5, 5));
+        tw.advance(500 * MS);
+        assert!(tw.is_done());
+        assert_eq!(tw.value(), (10, 0));
+
+        let mid = Color::Black.lerp(&Color::Rgb(200, 100, 0), 0.5);
+        assert_eq!(mid, Color::Rgb(100, 50, 0));
+    }
+
+    #[test]
+    fn keyframes_sequence() {
+        let mut kf = Keyframes::new(0.0f32)
+            .then(10.0, 10 * MS, Easing::Linear)
+            .hold(10 * MS)
+            .then(0.0, 10 * MS, Easing::Linear);
+        kf.advance(15 * MS);
+        assert_eq!(kf.value(), 10.0);
+        kf.advance(10 * MS);
+        assert!((kf.value() - 5.0).abs() < 1e-3);
+        kf.advance(10 * MS);
+        assert!(kf.is_done());
+
+        let mut kf = Keyframes::new(0.0f32)
+            .then(1.0, 10 * MS, Easing::Linear)
+            .looped();
+        kf.advance(25 * MS);
+        assert!(!kf.is_done());
+        assert!((kf.value() - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn scheduler_delay_and_finish() {
+        let mut sched = Scheduler::new();
+        let a = sched.start(Tween::new(0.0f32, 1.0, 10 * MS));
+        let b = sched.start_after(10 * MS, Tween::new(0.0f32, 1.0, 10 * MS));
+
+        assert_eq!(sched.tick(10 * MS), vec![a]);
+        assert_eq!(sched.value(a), Some(1.0));
+        assert_eq!(sched.value(b), Some(0.0));
+        assert!(sched.is_running(b) && !sched.is_idle());
+
+        assert_eq!(sched.tick(10 * MS), vec![b]);
+        assert!(sched.is_idle());
+        sched.remove(a);
+        assert_eq!(sched.value(a), None);
+    }
+
+    #[test]
+    fn transitions() {
+        let size = Size::new(4, 4);
+        let from = Image::filled(size, false);
+        let to = Image::filled(size, true);
+
+        let half = Transition::WipeRight.blend(&from, &to, 0.5);
+        assert_eq!(half.rows().next().unwrap(), &[true, true, false, false]);
+
+        for kind in [Transition::WipeDown, Transition::Dissolve, Transition::Fade] {
+            assert!(kind.blend(&from, &to, 0.0).pixels.iter().all(|p| !p));
+            assert!(kind.blend(&from, &to, 1.0).pixels.iter().all(|p| *p));
+        }
+        let fade = Transition::Fade.blend(&from, &to, 0.5);
+        assert_eq!(fade.pixels.iter().filter(|p| **p).count(), 8);
+    }
+}
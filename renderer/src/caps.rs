@@ -0,0 +1,277 @@
+use std::{
+    env,
+    io::{self, ErrorKind, Read, Write},
+    time::{Duration, Instant},
+};
+
+use crate::{error::Error, hr_bw_display::Res};
+
+/// synchronized output mode report request (DECRQM 2026)
+const QUERY_SYNC: &[u8] = b"\x1B[?2026$p";
+/// terminal name and version (XTVERSION)
+const QUERY_VERSION: &[u8] = b"\x1B[>0q";
+/// termcap lookup of `Tc` and `RGB` (XTGETTCAP), names are hex encoded
+const QUERY_TRUECOLOR: &[u8] = b"\x1BP+q5463;524742\x1B\\";
+/// kitty graphics protocol query for a 1x1 image, answered only by supporting terminals
+const QUERY_KITTY: &[u8] = b"\x1B_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1B\\";
+/// primary device attributes (DA1), answered by every terminal so it marks the end of replies
+const QUERY_DA1: &[u8] = b"\x1B[c";
+
+/// terminals known to draw the legacy computing sextants (U+1FB00)
+const SEXTANT_TERMS: &[&str] = &["kitty", "wezterm", "foot", "contour", "konsole", "ghostty"];
+/// terminals known to honour synchronized updates
+const SYNC_TERMS: &[&str] = &[
+    "kitty",
+    "wezterm",
+    "foot",
+    "contour",
+    "iterm",
+    "alacritty",
+    "ghostty",
+];
+
+/// what the terminal we are running in can display
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Capabilities {
+    pub truecolor: bool,
+    pub colors_256: bool,
+    /// half and full blocks, box drawing
+    pub blocks: bool,
+    pub sextants: bool,
+    pub braille: bool,
+    pub sync_output: bool,
+    pub sixel: bool,
+    pub kitty_graphics: bool,
+    /// name and version reported by XTVERSION
+    pub version: Option<String>,
+}
+
+impl Capabilities {
+    /// best guess from the environment alone, never touches the terminal
+    pub fn from_env() -> Self {
+        Self::from_vars(|k| env::var(k).ok())
+    }
+
+    fn from_vars<F>(var: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let term = var("TERM").unwrap_or_default().to_lowercase();
+        let program = var("TERM_PROGRAM").unwrap_or_default().to_lowercase();
+        let colorterm = var("COLORTERM").unwrap_or_default().to_lowercase();
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .find_map(|k| var(k).filter(|v| !v.is_empty()))
+            .unwrap_or_default()
+            .to_lowercase();
+
+        let known = |list: &[&str]| list.iter().any(|t| term.contains(t) || program.contains(t));
+        let kitty = term.contains("kitty") || var("KITTY_WINDOW_ID").is_some();
+        let wezterm = program.contains("wezterm");
+        let utf8 = locale.contains("utf-8") || locale.contains("utf8");
+        let console = term == "linux";
+
+        let truecolor = colorterm == "truecolor" || colorterm == "24bit";
+        Self {
+            truecolor,
+            colors_256: truecolor || term.contains("256color"),
+            blocks: utf8 || console,
+            sextants: utf8 && (known(SEXTANT_TERMS) || kitty),
+            braille: utf8 && !console,
+            sync_output: known(SYNC_TERMS) || kitty || var("WT_SESSION").is_some(),
+            sixel: wezterm || term.contains("foot") || term.contains("mlterm"),
+            kitty_graphics: kitty || wezterm || program.contains("ghostty"),
+            version: None,
+        }
+    }
+
+    /// refines the guess by asking the terminal, the answers are read from `input`
+    /// on this thread until the terminal replied or `timeout` passed
+    ///
+    /// `input` has to be in raw mode and its reads should give up after a short while
+    /// with `WouldBlock` or `TimedOut`, like a tty with `VMIN` 0 and `VTIME` set, a read
+    /// that blocks holds the query up for as long as it does
+    pub fn query<R, W>(
+        mut self,
+        input: &mut R,
+        output: &mut W,
+        timeout: Duration,
+    ) -> io::Result<Self>
+    where
+        R: Read,
+        W: Write,
+    {
+        for q in [
+            QUERY_SYNC,
+            QUERY_VERSION,
+            QUERY_TRUECOLOR,
+            QUERY_KITTY,
+            QUERY_DA1,
+        ] {
+            output.write_all(q)?;
+        }
+        output.flush()?;
+
+        self.apply_replies(&read_replies(input, timeout));
+        Ok(self)
+    }
+
+    fn apply_replies(&mut self, replies: &[u8]) {
+        if let Some(mode) = between(replies, b"\x1B[?2026;", b"$y") {
+            self.sync_output = matches!(mode, b"1" | b"2");
+        }
+
+        if let Some(version) = between(replies, b"\x1BP>|", b"\x1B\\") {
+            let version = String::from_utf8_lossy(version).to_string();
+            let name = version.to_lowercase();
+            if SEXTANT_TERMS.iter().any(|t| name.contains(t)) {
+                self.sextants = self.braille;
+            }
+            self.version = Some(version);
+        }
+
+        if between(replies, b"\x1BP1+r", b"\x1B\\").is_some() {
+            self.truecolor = true;
+            self.colors_256 = true;
+        }
+
+        if let Some(reply) = between(replies, b"\x1B_Gi=31;", b"\x1B\\") {
+            self.kitty_graphics = reply == b"OK";
+        }
+
+        if let Some(attrs) = da1_attrs(replies) {
+            self.sixel |= attrs.split(|&b| b == b';').any(|a| a == b"4");
+        }
+    }
+
+    /// fails if the terminal lacks the glyphs `res` is drawn with
+    pub fn check_res(&self, res: &Res) -> Result<(), Error> {
+        match res {
+            Res::Extra if !self.braille => Err(Error::Unsupported("braille patterns")),
+            Res::High if !self.sextants => Err(Error::Unsupported("sextant glyphs")),
+            Res::Low if !self.blocks => Err(Error::Unsupported("block elements")),
+            _ => Ok(()),
+        }
+    }
+
+    /// finest resolution of `HighResBWScreen` the terminal can draw
+    pub fn best_res(&self) -> Res {
+        if self.braille {
+            Res::Extra
+        } else if self.sextants {
+            Res::High
+        } else {
+            Res::Low
+        }
+    }
+}
+
+/// bytes enclosed by `start` and `end`, the first occurence only
+fn between<'a>(data: &'a [u8], start: &[u8], end: &[u8]) -> Option<&'a [u8]> {
+    let from = data.windows(start.len()).position(|w| w == start)? + start.len();
+    let len = data[from..].windows(end.len()).position(|w| w == end)?;
+    Some(&data[from..from + len])
+}
+
+/// collects everything the terminal sends until the DA1 reply arrives, byte by byte
+/// so nothing typed after the replies is swallowed
+fn read_replies<R: Read>(input: &mut R, timeout: Duration) -> Vec<u8> {
+    let deadline = Instant::now() + timeout;
+    let mut out = Vec::new();
+    let mut buf = [0u8; 1];
+    while Instant::now() < deadline && da1_attrs(&out).is_none() {
+        match input.read(&mut buf) {
+            Ok(0) => break,
+            Ok(_) => out.push(buf[0]),
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted
+                ) => {}
+            Err(_) => break,
+        }
+    }
+    out
+}
+
+/// attributes of the DA1 reply the data ends with, e.g. `62;4;22` from `\x1B[?62;4;22c`
+fn da1_attrs(data: &[u8]) -> Option<&[u8]> {
+    let start = data.iter().rposition(|&b| b == 0x1B)?;
+    let attrs = data[start + 1..].strip_prefix(b"[?")?.strip_suffix(b"c")?;
+    attrs
+        .iter()
+        .all(|b| b.is_ascii_digit() || *b == b';')
+        .then_some(attrs)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{self, Cursor, Read},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use super::Capabilities;
+    use crate::hr_bw_display::Res;
+
+    #[test]
+    fn env_guess() {
+        let caps = Capabilities::from_vars(|k| match k {
+            "TERM" => Some("xterm-kitty".into()),
+            "COLORTERM" => Some("truecolor".into()),
+            "LANG" => Some("en_US.UTF-8".into()),
+            _ => None,
+        });
+        assert!(caps.truecolor && caps.colors_256 && caps.sync_output && caps.kitty_graphics);
+        assert!(matches!(caps.best_res(), Res::Extra));
+
+        let caps = Capabilities::from_vars(|k| match k {
+            "TERM" => Some("linux".into()),
+            _ => None,
+        });
+        assert!(caps.blocks && !caps.braille && !caps.truecolor && !caps.sync_output);
+        assert!(matches!(caps.best_res(), Res::Low));
+    }
+
+    #[test]
+    fn terminal_replies() {
+        let replies =
+            b"\x1B[?2026;2$y\x1BP>|WezTerm 20240203\x1B\\\x1BP1+r5463=\x1B\\\x1B[?62;4;22c";
+        let mut sent = Vec::new();
+
+        let caps = Capabilities::default()
+            .query(
+                &mut Cursor::new(replies.to_vec()),
+                &mut sent,
+                Duration::from_secs(1),
+            )
+            .unwrap();
+
+        assert!(sent.ends_with(b"\x1B[c"));
+        assert!(caps.sync_output && caps.truecolor && caps.sixel && !caps.kitty_graphics);
+        assert_eq!(caps.version.as_deref(), Some("WezTerm 20240203"));
+    }
+
+    /// tty that never answers, its reads time out like with `VTIME` set
+    struct Silent;
+
+    impl Read for Silent {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            thread::sleep(Duration::from_millis(1));
+            Err(io::ErrorKind::WouldBlock.into())
+        }
+    }
+
+    #[test]
+    fn silent_terminal_times_out() {
+        let mut sent = Vec::new();
+        let start = Instant::now();
+        let caps = Capabilities::default()
+            .query(&mut Silent, &mut sent, Duration::from_millis(10))
+            .unwrap();
+        assert_eq!(caps, Capabilities::default());
+        // the query gave up on its own thread, nothing is left reading
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
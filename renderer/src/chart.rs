@@ -0,0 +1,438 @@
+use crate::{
+    hr_bw_display::DOTS_TABLE,
+    text,
+    traits::SizedRenderble,
+    types::{Quad, Size},
+};
+
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const EIGHTHS: [char; 8] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+
+/// grid of braille cells, every cell holds 2x4 dots
+pub struct Canvas {
+    size: Size,
+    cells: Vec<u8>,
+}
+
+impl Canvas {
+    /// `size` is in cells
+    pub fn new(size: Size) -> Self {
+        Self {
+            size,
+            cells: vec![0; size.flatten()],
+        }
+    }
+
+    /// resolution in dots
+    pub fn dot_size(&self) -> Size {
+        Size::new(self.size.w * 2, self.size.h * 4)
+    }
+
+    /// dots outside the canvas are ignored
+    pub fn set(&mut self, x: usize, y: usize) {
+        let dots = self.dot_size();
+        if x >= dots.w || y >= dots.h {
+            return;
+        }
+        // same bit order the chunk collector produces, first dot is the most significant
+        let bit = 7 - ((y % 4) * 2 + x % 2);
+        self.cells[(y / 4) * self.size.w + x / 2] |= 1 << bit;
+    }
+
+    pub fn line(&mut self, from: (usize, usize), to: (usize, usize)) {
+        let (mut x, mut y) = (from.0 as isize, from.1 as isize);
+        let (x1, y1) = (to.0 as isize, to.1 as isize);
+        let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+        let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+        let mut err = dx + dy;
+
+        loop {
+            self.set(x as usize, y as usize);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    pub fn rows(&self) -> Vec<String> {
+        self.render_rows().map(|row| row.collect()).collect()
+    }
+}
+
+impl SizedRenderble for Canvas {
+    type Primitive = char;
+
+    fn size(&self) -> Size {
+        self.size
+    }
+
+    fn render_rows(&self) -> impl Iterator<Item = impl Iterator<Item = Self::Primitive>> {
+        self.cells
+            .chunks(self.size.w.max(1))
+            .map(|row| row.iter().map(|&c| DOTS_TABLE[c as usize]))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Plot {
+    /// consecutive points of a series are connected
+    #[default]
+    Line,
+    Scatter,
+}
+
+/// braille plot of one or more series with a labelled y axis on the left and x axis below
+pub struct Chart {
+    size: Size,
+    plot: Plot,
+    x_range: Option<(f64, f64)>,
+    y_range: Option<(f64, f64)>,
+    series: Vec<Vec<(f64, f64)>>,
+}
+
+impl Chart {
+    /// `size` is in cells and includes the axes and labels
+    pub fn new(size: Size) -> Self {
+        Self {
+            size,
+            plot: Plot::Line,
+            x_range: None,
+            y_range: None,
+            series: Vec::new(),
+        }
+    }
+
+    pub fn with_plot(mut self, plot: Plot) -> Self {
+        self.plot = plot;
+        self
+    }
+
+    /// fixes the x range, it follows the data otherwise
+    pub fn x_range(mut self, min: f64, max: f64) -> Self {
+        self.x_range = Some((min, max));
+        self
+    }
+
+    /// fixes the y range, it follows the data otherwise
+    pub fn y_range(mut self, min: f64, max: f64) -> Self {
+        self.y_range = Some((min, max));
+        self
+    }
+
+    /// adds a series plotted against its index
+    pub fn series(self, ys: &[f64]) -> Self {
+        self.points(
+            &ys.iter()
+                .enumerate()
+                .map(|(x, &y)| (x as f64, y))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// adds a series of `(x, y)` points, non finite ones are left out
+    pub fn points(mut self, points: &[(f64, f64)]) -> Self {
+        let points = points
+            .iter()
+            .filter(|(x, y)| x.is_finite() && y.is_finite())
+            .copied()
+            .collect();
+        self.series.push(points);
+        self
+    }
+
+    fn range(&self, fixed: Option<(f64, f64)>, axis: fn(&(f64, f64)) -> f64) -> (f64, f64) {
+        let (min, max) = fixed.unwrap_or_else(|| {
+            self.series
+                .iter()
+                .flatten()
+                .map(axis)
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+                    (lo.min(v), hi.max(v))
+                })
+        });
+        if !min.is_finite() || !max.is_finite() {
+            (0.0, 1.0)
+        } else if min >= max {
+            (min - 0.5, min + 0.5)
+        } else {
+            (min, max)
+        }
+    }
+
+    /// the rendered chart, empty if `size` leaves no room for the plot,
+    /// values outside the range are pinned to its border
+    pub fn rows(&self) -> Vec<String> {
+        let (x_min, x_max) = self.range(self.x_range, |p| p.0);
+        let (y_min, y_max) = self.range(self.y_range, |p| p.1);
+
+        let y_labels = [
+            fmt_num(y_max),
+            fmt_num((y_min + y_max) / 2.0),
+            fmt_num(y_min),
+        ];
+        // the middle label only shows up on taller plots
+        let plot_h = self.size.h.saturating_sub(2);
+        let shown = if plot_h >= 5 {
+            &y_labels[..]
+        } else {
+            &y_labels[..1]
+        };
+        let label_w = shown
+            .iter()
+            .chain(&y_labels[2..])
+            .map(|l| text::width(l))
+            .max()
+            .unwrap_or(0);
+        let plot = Size::new(self.size.w.saturating_sub(label_w + 1), plot_h);
+        if plot.flatten() == 0 {
+            return Vec::new();
+        }
+
+        let mut canvas = Canvas::new(plot);
+        let dots = canvas.dot_size();
+        let to_dot = |(x, y): (f64, f64)| {
+            let fx = ((x - x_min) / (x_max - x_min)).clamp(0.0, 1.0);
+            let fy = ((y - y_min) / (y_max - y_min)).clamp(0.0, 1.0);
+            (
+                (fx * (dots.w - 1) as f64).round() as usize,
+                dots.h - 1 - (fy * (dots.h - 1) as f64).round() as usize,
+            )
+        };
+
+        for series in &self.series {
+            let mut prev = None;
+            for &p in series {
+                let dot = to_dot(p);
+                match (self.plot, prev) {
+                    (Plot::Line, Some(prev)) => canvas.line(prev, dot),
+                    _ => canvas.set(dot.0, dot.1),
+                }
+                prev = Some(dot);
+            }
+        }
+
+        let mid = plot.h / 2;
+        let mut rows: Vec<String> = canvas
+            .rows()
+            .into_iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let label = match i {
+                    0 => Some(&y_labels[0]),
+                    i if i + 1 == plot.h => Some(&y_labels[2]),
+                    i if i == mid && plot.h >= 5 => Some(&y_labels[1]),
+                    _ => None,
+                };
+                match label {
+                    Some(l) => format!("{}┤{}", text::pad(l, label_w, Quad::Right), row),
+                    None => format!("{}│{}", " ".repeat(label_w), row),
+                }
+            })
+            .collect();
+
+        rows.push(format!("{}└{}", " ".repeat(label_w), "─".repeat(plot.w)));
+
+        let (lo, hi) = (fmt_num(x_min), fmt_num(x_max));
+        let hi_w = plot.w.saturating_sub(text::width(&lo));
+        rows.push(format!(
+            "{} {}{}",
+            " ".repeat(label_w),
+            lo,
+            text::pad(text::clip(&hi, hi_w), hi_w, Quad::Right)
+        ));
+        rows
+    }
+}
+
+/// compact label for an axis value
+fn fmt_num(v: f64) -> String {
+    let a = v.abs();
+    if a >= 1e6 {
+        format!("{:.1}M", v / 1e6)
+    } else if a >= 1e4 {
+        format!("{:.1}k", v / 1e3)
+    } else if a >= 100.0 || v.fract() == 0.0 {
+        format!("{:.0}", v)
+    } else if a >= 1.0 {
+        format!("{:.1}", v)
+    } else {
+        format!("{:.2}", v)
+    }
+}
+
+/// one glyph per value scaled between the smallest and largest one,
+/// longer input is averaged down to `width` glyphs
+pub fn sparkline(values: &[f64], width: usize) -> String {
+    let values: Vec<f64> = values.iter().filter(|v| v.is_finite()).copied().collect();
+    let n = values.len().min(width);
+    let buckets: Vec<f64> = (0..n)
+        .map(|i| {
+            let part = &values[i * values.len() / n..(i + 1) * values.len() / n];
+            part.iter().sum::<f64>() / part.len() as f64
+        })
+        .collect();
+
+    let min = buckets.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = buckets.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    buckets
+        .iter()
+        .map(|v| {
+            let level = if max > min {
+                ((v - min) / (max - min) * 7.0).round() as usize
+            } else {
+                0
+            };
+            SPARKS[level]
+        })
+        .collect()
+}
+
+/// horizontal bar of `value / max * width` cells in eighth cell steps, padded to `width`
+pub fn bar(value: f64, max: f64, width: usize) -> String {
+    let eighths = if max > 0.0 {
+        ((value / max).clamp(0.0, 1.0) * (width * 8) as f64).round() as usize
+    } else {
+        0
+    };
+
+    let mut out: String = std::iter::repeat_n('█', eighths / 8).collect();
+    if eighths % 8 > 0 {
+        out.push(EIGHTHS[eighths % 8 - 1]);
+    }
+    text::pad(&out, width, Quad::Left)
+}
+
+/// values counted into equally wide bins between their minimum and maximum
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub min: f64,
+    pub max: f64,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    pub fn new(values: &[f64], bins: usize) -> Self {
+        let bins = bins.max(1);
+        let finite = values.iter().filter(|v| v.is_finite());
+        let min = finite.clone().copied().fold(f64::INFINITY, f64::min);
+        let max = finite.clone().copied().fold(f64::NEG_INFINITY, f64::max);
+
+        let mut counts = vec![0; bins];
+        for v in finite {
+            let i = if max > min {
+                ((v - min) / (max - min) * bins as f64) as usize
+            } else {
+                0
+            };
+            counts[i.min(bins - 1)] += 1;
+        }
+
+        Self { min, max, counts }
+    }
+
+    /// lower and upper bound of bin `i`
+    pub fn bin_range(&self, i: usize) -> (f64, f64) {
+        let step = (self.max - self.min) / self.counts.len() as f64;
+        (self.min + step * i as f64, self.min + step * (i + 1) as f64)
+    }
+
+    /// one bar per bin labelled with its lower bound and count, every row is `width` wide
+    pub fn rows(&self, width: usize) -> Vec<String> {
+        let labels: Vec<String> = (0..self.counts.len())
+            .map(|i| fmt_num(self.bin_range(i).0))
+            .collect();
+        let label_w = labels.iter().map(|l| text::width(l)).max().unwrap_or(0);
+        let peak = self.counts.iter().copied().max().unwrap_or(0);
+        let count_w = peak.to_string().len();
+        let bar_w = width.saturating_sub(label_w + count_w + 2);
+
+        labels
+            .iter()
+            .zip(&self.counts)
+            .map(|(label, &n)| {
+                let row = format!(
+                    "{}│{} {}",
+                    text::pad(label, label_w, Quad::Right),
+                    bar(n as f64, peak as f64, bar_w),
+                    n
+                );
+                text::pad(text::clip(&row, width), width, Quad::Left)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Canvas, Chart, Histogram, Plot, bar, sparkline};
+    use crate::types::Size;
+
+    #[test]
+    fn canvas_dots() {
+        let mut canvas = Canvas::new(Size::new(2, 1));
+        canvas.set(0, 0);
+        canvas.set(3, 3);
+        canvas.set(9, 9);
+        assert_eq!(canvas.rows(), vec!["⠁⢀"]);
+
+        let mut canvas = Canvas::new(Size::new(1, 1));
+        canvas.line((0, 0), (0, 3));
+        assert_eq!(canvas.rows(), vec!["⡇"]);
+    }
+
+    #[test]
+    fn chart_layout() {
+        let rows = Chart::new(Size::new(10, 6))
+            .series(&[0.0, 1.0, 2.0, 3.0])
+            .rows();
+        assert_eq!(rows.len(), 6);
+        assert!(rows[0].starts_with("3┤"));
+        assert!(rows[3].starts_with("0┤"));
+        assert_eq!(rows[4], " └────────");
+        assert_eq!(rows[5], "  0      3");
+
+        let rows = Chart::new(Size::new(10, 6))
+            .with_plot(Plot::Scatter)
+            .points(&[(0.0, 0.0), (1.0, 1.0)])
+            .rows();
+        assert!(rows[0].ends_with('⠈'));
+        assert!(rows[3].contains('⡀'));
+
+        assert!(Chart::new(Size::new(3, 2)).series(&[1.0]).rows().is_empty());
+    }
+
+    #[test]
+    fn sparkline_levels() {
+        let values: Vec<f64> = (0..8).map(|v| v as f64).collect();
+        assert_eq!(sparkline(&values, 8), "▁▂▃▄▅▆▇█");
+        assert_eq!(sparkline(&values, 2), "▁█");
+        assert_eq!(sparkline(&[5.0, 5.0], 4), "▁▁");
+    }
+
+    #[test]
+    fn eighth_bars() {
+        assert_eq!(bar(1.0, 2.0, 3), "█▌ ");
+        assert_eq!(bar(0.0, 0.0, 2), "  ");
+        assert_eq!(bar(9.0, 2.0, 2), "██");
+    }
+
+    #[test]
+    fn histogram_bins() {
+        let hist = Histogram::new(&[0.0, 1.0, 1.5, 4.0], 2);
+        assert_eq!(hist.counts, vec![3, 1]);
+        assert_eq!(hist.bin_range(1), (2.0, 4.0));
+
+        let rows = hist.rows(10);
+        assert_eq!(rows[0], "0│██████ 3");
+        assert_eq!(rows[1], "2│██     1");
+    }
+}
@@ -0,0 +1,48 @@
+use std::{error, fmt, io};
+
+use crate::types::Size;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// a dimension the target cannot work with, e.g. zero width
+    InvalidSize(Size),
+    /// the terminal lacks a feature the target needs
+    Unsupported(&'static str),
+    /// the drawn content does not fit the target, for flat input `found.w` is the
+    /// length of the last row
+    SizeMismatch {
+        expected: Size,
+        found: Size,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "i/o error: {}", e),
+            Self::InvalidSize(s) => write!(f, "invalid size {}x{}", s.w, s.h),
+            Self::Unsupported(what) => write!(f, "terminal does not support {}", what),
+            Self::SizeMismatch { expected, found } => write!(
+                f,
+                "size mismatch: expected {}x{}, found {}x{}",
+                expected.w, expected.h, found.w, found.h
+            ),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
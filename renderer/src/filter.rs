@@ -0,0 +1,496 @@
+use crate::{image::Image, types::Size};
+
+/// tone curve applied before thresholding
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Levels {
+    /// input at or below becomes black
+    pub black: u8,
+    /// input at or above becomes white
+    pub white: u8,
+    /// above 1 brightens the mid tones
+    pub gamma: f32,
+    /// scales the distance from mid gray, 1 keeps it
+    pub contrast: f32,
+}
+
+impl Default for Levels {
+    fn default() -> Self {
+        Self {
+            black: 0,
+            white: 255,
+            gamma: 1.0,
+            contrast: 1.0,
+        }
+    }
+}
+
+pub fn levels(img: &Image<u8>, lv: &Levels) -> Image<u8> {
+    let range = (lv.white.saturating_sub(lv.black)).max(1) as f32;
+    let lut: Vec<u8> = (0..=255u8)
+        .map(|v| {
+            let v = (v.saturating_sub(lv.black) as f32 / range).min(1.0);
+            let v = ((v - 0.5) * lv.contrast + 0.5).clamp(0.0, 1.0);
+            let v = v.powf(1.0 / lv.gamma.max(0.01));
+            (v * 255.0).round() as u8
+        })
+        .collect();
+    img.map(|&v| lut[v as usize])
+}
+
+pub fn threshold(img: &Image<u8>, t: u8) -> Image<bool> {
+    img.map(|&v| v >= t)
+}
+
+/// sets a pixel when it is brighter than the mean of its `(2 * radius + 1)^2` neighbourhood
+/// minus `offset`, keeps detail in unevenly lit frames
+pub fn adaptive_threshold(img: &Image<u8>, radius: usize, offset: i16) -> Image<bool> {
+    let Size { w, h } = img.size;
+
+    // summed area table with an extra zero row and column
+    let mut sat = vec![0u64; (w + 1) * (h + 1)];
+    for y in 0..h {
+        let mut row = 0u64;
+        for x in 0..w {
+            row += *img.get(x, y) as u64;
+            sat[(y + 1) * (w + 1) + x + 1] = sat[y * (w + 1) + x + 1] + row;
+        }
+    }
+
+    let mut pixels = Vec::with_capacity(img.size.flatten());
+    for y in 0..h {
+        let (y0, y1) = (
+            y.saturating_sub(radius),
+            y.saturating_add(radius).min(h - 1) + 1,
+        );
+        for x in 0..w {
+            let (x0, x1) = (
+                x.saturating_sub(radius),
+                x.saturating_add(radius).min(w - 1) + 1,
+            );
+            let sum = sat[y1 * (w + 1) + x1] + sat[y0 * (w + 1) + x0]
+                - sat[y0 * (w + 1) + x1]
+                - sat[y1 * (w + 1) + x0];
+            let mean = (sum / ((x1 - x0) * (y1 - y0)) as u64) as i32;
+            pixels.push(*img.get(x, y) as i32 > mean - offset as i32);
+        }
+    }
+    Image::new(img.size, pixels)
+}
+
+/// horizontal and vertical Sobel responses, borders are clamped
+fn gradients(img: &Image<u8>) -> (Vec<f32>, Vec<f32>) {
+    let Size { w, h } = img.size;
+    let at = |x: isize, y: isize| {
+        let x = x.clamp(0, w as isize - 1) as usize;
+        let y = y.clamp(0, h as isize - 1) as usize;
+        *img.get(x, y) as f32
+    };
+
+    let mut gx = Vec::with_capacity(w * h);
+    let mut gy = Vec::with_capacity(w * h);
+    for y in 0..h as isize {
+        for x in 0..w as isize {
+            gx.push(
+                at(x + 1, y - 1) + 2.0 * at(x + 1, y) + at(x + 1, y + 1)
+                    - at(x - 1, y - 1)
+                    - 2.0 * at(x - 1, y)
+                    - at(x - 1, y + 1),
+            );
+            gy.push(
+                at(x - 1, y + 1) + 2.0 * at(x, y + 1) + at(x + 1, y + 1)
+                    - at(x - 1, y - 1)
+                    - 2.0 * at(x, y - 1)
+                    - at(x + 1, y - 1),
+            );
+        }
+    }
+    (gx, gy)
+}
+
+/// gradient magnitude, clamped to 255
+pub fn sobel(img: &Image<u8>) -> Image<u8> {
+    let (gx, gy) = gradients(img);
+    let pixels = gx
+        .iter()
+        .zip(&gy)
+        .map(|(x, y)| x.hypot(*y).min(255.0) as u8)
+        .collect();
+    Image::new(img.size, pixels)
+}
+
+/// Canny-style edges: Sobel gradients thinned by non-maximum suppression, then hysteresis,
+/// pixels above `high` are edges and pixels above `low` are when connected to one
+pub fn edges(img: &Image<u8>, low: u8, high: u8) -> Image<bool> {
+    let Size { w, h } = img.size;
+    if w == 0 || h == 0 {
+        return Image::new(img.size, Vec::new());
+    }
+
+    let (gx, gy) = gradients(img);
+    let mag: Vec<f32> = gx.iter().zip(&gy).map(|(x, y)| x.hypot(*y)).collect();
+    let mag_at = |x: isize, y: isize| {
+        if x < 0 || y < 0 || x >= w as isize || y >= h as isize {
+            0.0
+        } else {
+            mag[y as usize * w + x as usize]
+        }
+    };
+
+    // 0 none, 1 weak, 2 strong
+    let mut class = vec![0u8; w * h];
+    for y in 0..h {
+        for x in 0..w {
+            let i = y * w + x;
+            let m = mag[i];
+            if m <= low as f32 {
+                continue;
+            }
+
+            // neighbours across the edge, the gradient direction rounded to 45 degrees
+            let angle = gy[i].atan2(gx[i]).to_degrees().rem_euclid(180.0);
+            let (dx, dy) = match angle {
+                a if !(22.5..157.5).contains(&a) => (1, 0),
+                a if a < 67.5 => (1, 1),
+                a if a < 112.5 => (0, 1),
+                _ => (-1, 1),
+            };
+            let (x, y) = (x as isize, y as isize);
+            if m < mag_at(x + dx, y + dy) || m < mag_at(x - dx, y - dy) {
+                continue;
+            }
+
+            class[i] = 1 + (m > high as f32) as u8;
+        }
+    }
+
+    let mut out = vec![false; w * h];
+    let mut stack: Vec<usize> = (0..w * h).filter(|&i| class[i] == 2).collect();
+    while let Some(i) = stack.pop() {
+        if out[i] {
+            continue;
+        }
+        out[i] = true;
+
+        let (x, y) = (i % w, i / w);
+        for ny in y.saturating_sub(1)..(y + 2).min(h) {
+            for nx in x.saturating_sub(1)..(x + 2).min(w) {
+                let n = ny * w + nx;
+                if class[n] > 0 && !out[n] {
+                    stack.push(n);
+                }
+            }
+        }
+    }
+
+    Image::new(img.size, out)
+}
+
+/// a pixel takes `grow` if any pixel of the square neighbourhood has it
+fn morph(img: &Image<bool>, radius: usize, grow: bool) -> Image<bool> {
+    let Size { w, h } = img.size;
+
+    // separable: rows first, then columns
+    let mut tmp = vec![!grow; w * h];
+    for y in 0..h {
+        for x in 0..w {
+            let (x0, x1) = (
+                x.saturating_sub(radius),
+                x.saturating_add(radius).min(w - 1) + 1,
+            );
+            tmp[y * w + x] = (x0..x1).any(|nx| *img.get(nx, y) == grow) == grow;
+        }
+    }
+
+    let mut pixels = vec![!grow; w * h];
+    for y in 0..h {
+        let (y0, y1) = (
+            y.saturating_sub(radius),
+            y.saturating_add(radius).min(h - 1) + 1,
+        );
+        for x in 0..w {
+            pixels[y * w + x] = (y0..y1).any(|ny| tmp[ny * w + x] == grow) == grow;
+        }
+    }
+    Image::new(img.size, pixels)
+}
+
+pub fn dilate(img: &Image<bool>, radius: usize) -> Image<bool> {
+    morph(img, radius, true)
+}
+
+pub fn erode(img: &Image<bool>, radius: usize) -> Image<bool> {
+    morph(img, radius, false)
+}
+
+/// how gray levels are spread into black and white pixels
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Dither {
+    /// error diffusion, the finest detail but noisy in motion
+    FloydSteinberg,
+    /// ordered with a `n` by `n` Bayer matrix, `n` is a power of two up to 16
+    Bayer(usize),
+}
+
+/// error diffusion onto the right and lower neighbours
+pub fn floyd_steinberg(img: &Image<u8>) -> Image<bool> {
+    let Size { w, h } = img.size;
+    // errors of the current and the next row, one extra cell on both sides
+    let mut cur = vec![0i32; w + 2];
+    let mut next = vec![0i32; w + 2];
+
+    let mut pixels = Vec::with_capacity(img.size.flatten());
+    for y in 0..h {
+        for x in 0..w {
+            let v = *img.get(x, y) as i32 + cur[x + 1] / 16;
+            let on = v >= 128;
+            let err = v - if on { 255 } else { 0 };
+
+            cur[x + 2] += err * 7;
+            next[x] += err * 3;
+            next[x + 1] += err * 5;
+            next[x + 2] += err;
+            pixels.push(on);
+        }
+        std::mem::swap(&mut cur, &mut next);
+        next.iter_mut().for_each(|e| *e = 0);
+    }
+    Image::new(img.size, pixels)
+}
+
+/// `n` by `n` threshold map with values `0..n * n`
+fn bayer_matrix(n: usize) -> Vec<usize> {
+    let mut m = vec![0];
+    let mut size = 1;
+    while size < n {
+        let mut grown = vec![0; size * size * 4];
+        for y in 0..size {
+            for x in 0..size {
+                let v = m[y * size + x] * 4;
+                let row = 2 * size;
+                grown[y * row + x] = v;
+                grown[y * row + x + size] = v + 2;
+                grown[(y + size) * row + x] = v + 3;
+                grown[(y + size) * row + x + size] = v + 1;
+            }
+        }
+        m = grown;
+        size *= 2;
+    }
+    m
+}
+
+pub fn bayer(img: &Image<u8>, n: usize) -> Image<bool> {
+    let n = n.clamp(1, 16).next_power_of_two();
+    let thresholds: Vec<f32> = bayer_matrix(n)
+        .iter()
+        .map(|&m| (m as f32 + 0.5) / (n * n) as f32 * 255.0)
+        .collect();
+
+    let w = img.size.w;
+    let pixels = img
+        .pixels
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| v as f32 > thresholds[(i / w % n) * n + i % w % n])
+        .collect();
+    Image::new(img.size, pixels)
+}
+
+pub fn dither(img: &Image<u8>, d: Dither) -> Image<bool> {
+    match d {
+        Dither::FloydSteinberg => floyd_steinberg(img),
+        Dither::Bayer(n) => bayer(img, n),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    Levels(Levels),
+    Threshold(u8),
+    Adaptive { radius: usize, offset: i16 },
+    Edges { low: u8, high: u8 },
+    Dilate(usize),
+    Erode(usize),
+    Dither(Dither),
+}
+
+enum Working {
+    Gray(Image<u8>),
+    Bits(Image<bool>),
+}
+
+impl Working {
+    fn gray(self) -> Image<u8> {
+        match self {
+            Self::Gray(img) => img,
+            Self::Bits(img) => img.map(|&b| if b { 255 } else { 0 }),
+        }
+    }
+
+    fn bits(self) -> Image<bool> {
+        match self {
+            Self::Gray(img) => threshold(&img, 128),
+            Self::Bits(img) => img,
+        }
+    }
+}
+
+/// filters applied one after another, grayscale filters see 1-bit input as black and white
+/// and grayscale output is thresholded at mid gray wherever bits are needed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FilterChain {
+    filters: Vec<Filter>,
+}
+
+impl FilterChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn then(mut self, f: Filter) -> Self {
+        self.filters.push(f);
+        self
+    }
+
+    pub fn push(&mut self, f: Filter) {
+        self.filters.push(f);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    pub fn apply_gray(&self, img: Image<u8>) -> Image<bool> {
+        self.run(Working::Gray(img))
+    }
+
+    pub fn apply_bits(&self, img: Image<bool>) -> Image<bool> {
+        self.run(Working::Bits(img))
+    }
+
+    fn run(&self, img: Working) -> Image<bool> {
+        self.filters
+            .iter()
+            .fold(img, |img, f| match *f {
+                Filter::Levels(lv) => Working::Gray(levels(&img.gray(), &lv)),
+                Filter::Threshold(t) => Working::Bits(threshold(&img.gray(), t)),
+                Filter::Adaptive { radius, offset } => {
+                    Working::Bits(adaptive_threshold(&img.gray(), radius, offset))
+                }
+                Filter::Edges { low, high } => Working::Bits(edges(&img.gray(), low, high)),
+                Filter::Dilate(r) => Working::Bits(dilate(&img.bits(), r)),
+                Filter::Erode(r) => Working::Bits(erode(&img.bits(), r)),
+                Filter::Dither(d) => Working::Bits(dither(&img.gray(), d)),
+            })
+            .bits()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Dither, Filter, FilterChain, Levels, adaptive_threshold, bayer, bayer_matrix, dilate,
+        edges, erode, floyd_steinberg, levels, sobel,
+    };
+    use crate::{image::Image, types::Size};
+
+    /// dark left half, bright right half
+    fn step(w: usize, h: usize) -> Image<u8> {
+        let pixels = (0..w * h)
+            .map(|i| if i % w < w / 2 { 20 } else { 220 })
+            .collect();
+        Image::new(Size::new(w, h), pixels)
+    }
+
+    #[test]
+    fn sobel_and_edges() {
+        let img = step(8, 4);
+        let mag = sobel(&img);
+        assert_eq!(*mag.get(0, 1), 0);
+        assert_eq!(*mag.get(4, 1), 255);
+
+        let e = edges(&img, 50, 150);
+        for y in 0..4 {
+            let row: Vec<bool> = e.rows().nth(y).unwrap().to_vec();
+            assert_eq!(
+                row.iter().filter(|b| **b).count(),
+                2,
+                "thin edge in row {y}"
+            );
+            assert!(row[3] && row[4]);
+        }
+    }
+
+    #[test]
+    fn morphology() {
+        let mut img = Image::filled(Size::new(5, 5), false);
+        img.pixels[12] = true;
+
+        let grown = dilate(&img, 1);
+        assert_eq!(grown.pixels.iter().filter(|b| **b).count(), 9);
+        assert!(*grown.get(1, 1) && !*grown.get(0, 0));
+
+        let shrunk = erode(&grown, 1);
+        assert_eq!(shrunk, img);
+
+        // a window past every edge covers the whole image
+        assert!(dilate(&img, usize::MAX).pixels.iter().all(|b| *b));
+    }
+
+    #[test]
+    fn tone_and_adaptive() {
+        let img = Image::new(Size::new(4, 1), vec![0, 64, 128, 255]);
+        let lv = Levels {
+            black: 64,
+            white: 128,
+            ..Default::default()
+        };
+        assert_eq!(levels(&img, &lv).pixels, vec![0, 0, 255, 255]);
+
+        // a faint detail on a bright gradient survives adaptive thresholding
+        let mut img = Image::new(Size::new(9, 1), (0..9).map(|x| 150 + x * 10).collect());
+        img.pixels[4] = 120;
+        let bits = adaptive_threshold(&img, 2, 0);
+        assert!(!bits.pixels[4]);
+        assert!(bits.pixels[8]);
+        assert_eq!(adaptive_threshold(&img, usize::MAX, 0).size, img.size);
+    }
+
+    #[test]
+    fn chain_mixes_gray_and_bits() {
+        let chain = FilterChain::new()
+            .then(Filter::Edges { low: 50, high: 150 })
+            .then(Filter::Dilate(1));
+        let out = chain.apply_gray(step(8, 4));
+        assert_eq!(
+            out.rows().next().unwrap(),
+            [false, false, true, true, true, true, false, false]
+        );
+
+        let bits = Image::new(Size::new(3, 1), vec![false, true, false]);
+        let out = FilterChain::new().then(Filter::Erode(1)).apply_bits(bits);
+        assert_eq!(out.pixels, vec![false; 3]);
+    }
+
+    #[test]
+    fn dither_keeps_mean() {
+        assert_eq!(bayer_matrix(2), [0, 2, 3, 1]);
+        let m = bayer_matrix(8);
+        let mut sorted = m.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..64).collect::<Vec<_>>());
+
+        let gray = Image::filled(Size::new(16, 16), 64u8);
+        let on = |img: Image<bool>| img.pixels.iter().filter(|p| **p).count();
+        assert_eq!(on(bayer(&gray, 4)), 64);
+        // some error leaves over the right edge
+        assert!((56..=64).contains(&on(floyd_steinberg(&gray))));
+
+        let chain = FilterChain::new().then(Filter::Dither(Dither::Bayer(2)));
+        assert_eq!(
+            on(chain.apply_gray(Image::filled(Size::new(4, 4), 255))),
+            16
+        );
+        assert_eq!(on(chain.apply_gray(Image::filled(Size::new(4, 4), 0))), 0);
+    }
+}
@@ -0,0 +1,451 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+/// source of time for `GameLoop`, swapped for a fake one in tests
+pub trait Clock {
+    /// time since some fixed point, never goes backwards
+    fn now(&self) -> Duration;
+    fn sleep_until(&mut self, deadline: Duration);
+}
+
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn sleep_until(&mut self, deadline: Duration) {
+        thread::sleep(deadline.saturating_sub(self.now()));
+    }
+}
+
+/// clock that only moves when slept on or advanced by hand
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    pub now: Duration,
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        self.now
+    }
+
+    fn sleep_until(&mut self, deadline: Duration) {
+        self.now = self.now.max(deadline);
+    }
+}
+
+/// what the game wants the loop to do next
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Control {
+    #[default]
+    Continue,
+    /// stops calling `update`, frames are still rendered
+    Pause,
+    Resume,
+    Stop,
+}
+
+pub trait Game {
+    type Error;
+
+    /// called once per iteration before any update, e.g. to read input
+    fn poll(&mut self) -> Control {
+        Control::Continue
+    }
+
+    /// advances the game by exactly one fixed step
+    fn update(&mut self, step: Duration) -> Result<Control, Self::Error>;
+
+    /// time until the next update, asked after every update, `None` keeps the loop's step
+    fn next_step(&self) -> Option<Duration> {
+        None
+    }
+
+    /// draws the current state, `alpha` is how far time got towards the next update in `[0, 1)`
+    fn render(&mut self, alpha: f32, stats: &LoopStats) -> Result<(), Self::Error>;
+}
+
+/// counters and timings collected while the loop runs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoopStats {
+    pub updates: u64,
+    pub frames: u64,
+    /// updates given up on because the loop fell too far behind
+    pub skipped_updates: u64,
+    /// frames rendered after their deadline had already passed
+    pub late_frames: u64,
+    /// time between the starts of the last two frames
+    pub last_interval: Duration,
+    /// time the last `render` call took
+    pub last_render: Duration,
+    pub paused: bool,
+}
+
+/// fixed timestep loop with absolute deadlines, updates run at a fixed rate while
+/// frames are rendered at their own rate or after every round of updates
+pub struct GameLoop<C: Clock = SystemClock> {
+    clock: C,
+    step: Duration,
+    render_step: Option<Duration>,
+    max_catch_up: u32,
+    next_update: Option<Duration>,
+    next_render: Duration,
+    last_frame: Option<Duration>,
+    stats: LoopStats,
+}
+
+impl GameLoop {
+    pub fn new(step: Duration) -> Self {
+        Self::with_clock(step, SystemClock::default())
+    }
+
+    /// `rate` updates per second
+    pub fn from_rate(rate: u64) -> Self {
+        Self::new(Duration::from_nanos(1_000_000_000 / rate.max(1)))
+    }
+}
+
+impl<C: Clock> GameLoop<C> {
+    pub fn with_clock(step: Duration, clock: C) -> Self {
+        Self {
+            clock,
+            step: step.max(Duration::from_micros(1)),
+            render_step: None,
+            max_catch_up: 5,
+            next_update: None,
+            next_render: Duration::ZERO,
+            last_frame: None,
+            stats: LoopStats::default(),
+        }
+    }
+
+    /// renders at `fps` frames per second instead of after every round of updates
+    pub fn with_render_rate(mut self, fps: u64) -> Self {
+        self.render_step = Some(Duration::from_nanos(1_000_000_000 / fps.max(1)));
+        self
+    }
+
+    /// most updates run in one iteration to catch up, the rest are skipped
+    pub fn with_max_catch_up(mut self, n: u32) -> Self {
+        self.max_catch_up = n.max(1);
+        self
+    }
+
+    /// changes the update step, takes effect after the next update
+    pub fn set_step(&mut self, step: Duration) {
+        self.step = step.max(Duration::from_micros(1));
+    }
+
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    pub fn pause(&mut self) {
+        self.stats.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.stats.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.stats.paused
+    }
+
+    pub fn stats(&self) -> &LoopStats {
+        &self.stats
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// runs until the game asks to stop
+    pub fn run<G: Game>(&mut self, game: &mut G) -> Result<(), G::Error> {
+        while self.tick(game)? {}
+        Ok(())
+    }
+
+    /// one iteration: poll, due updates, a frame if one is due, then sleep until the
+    /// next deadline, false once the game asked to stop
+    pub fn tick<G: Game>(&mut self, game: &mut G) -> Result<bool, G::Error> {
+        let now = self.clock.now();
+        // deadlines count from the first tick, not from when the loop was built
+        let mut next_update = *self.next_update.get_or_insert_with(|| {
+            self.next_render = now;
+            now
+        });
+
+        if !self.control(game.poll()) {
+            return Ok(false);
+        }
+
+        let mut rounds = 0;
+        let mut step = self.step;
+        while now >= next_update && rounds < self.max_catch_up {
+            step = self.step;
+            if !self.stats.paused {
+                let ctrl = game.update(self.step)?;
+                self.stats.updates += 1;
+                if !self.control(ctrl) {
+                    return Ok(false);
+                }
+                step = game.next_step().unwrap_or(self.step);
+            }
+            next_update += step;
+            rounds += 1;
+        }
+
+        // the updates given up on would have kept the step the game last asked for
+        if now >= next_update {
+            let behind = ((now - next_update).as_nanos() / step.as_nanos()) as u32 + 1;
+            if !self.stats.paused {
+                self.stats.skipped_updates += behind as u64;
+            }
+            next_update += step * behind;
+        }
+        self.next_update = Some(next_update);
+
+        let render_due = match self.render_step {
+            Some(_) => now >= self.next_render,
+            None => rounds > 0,
+        };
+        if render_due {
+            self.render(game, now, next_update)?;
+        }
+
+        let deadline = match self.render_step {
+            Some(_) => next_update.min(self.next_render),
+            None => next_update,
+        };
+        self.clock.sleep_until(deadline);
+        Ok(true)
+    }
+
+    fn control(&mut self, ctrl: Control) -> bool {
+        match ctrl {
+            Control::Continue => {}
+            Control::Pause => self.pause(),
+            Control::Resume => self.resume(),
+            Control::Stop => return false,
+        }
+        true
+    }
+
+    fn render<G: Game>(
+        &mut self,
+        game: &mut G,
+        now: Duration,
+        next_update: Duration,
+    ) -> Result<(), G::Error> {
+        if let Some(last) = self.last_frame {
+            self.stats.last_interval = now - last;
+        }
+        self.last_frame = Some(now);
+
+        let until_update = next_update.saturating_sub(now).as_secs_f32();
+        let alpha = (1.0 - until_update / self.step.as_secs_f32()).clamp(0.0, 1.0);
+        game.render(alpha, &self.stats)?;
+        self.stats.frames += 1;
+        self.stats.last_render = self.clock.now().saturating_sub(now);
+
+        if let Some(render_step) = self.render_step {
+            self.next_render += render_step;
+            if self.next_render <= now {
+                self.stats.late_frames += 1;
+                self.next_render = now + render_step;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, time::Duration};
+
+    use super::{Control, Game, GameLoop, LoopStats, ManualClock};
+
+    const MS: Duration = Duration::from_millis(1);
+
+    /// counts calls and stops after `limit` updates
+    struct Counter {
+        updates: u32,
+        frames: u32,
+        limit: u32,
+    }
+
+    impl Counter {
+        fn new(limit: u32) -> Self {
+            Self {
+                updates: 0,
+                frames: 0,
+                limit,
+            }
+        }
+    }
+
+    impl Game for Counter {
+        type Error = Infallible;
+
+        fn update(&mut self, _step: Duration) -> Result<Control, Infallible> {
+            self.updates += 1;
+            Ok(if self.updates >= self.limit {
+                Control::Stop
+            } else {
+                Control::Continue
+            })
+        }
+
+        fn render(&mut self, _alpha: f32, _stats: &LoopStats) -> Result<(), Infallible> {
+            self.frames += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn fixed_rate_without_drift() {
+        let mut game = Counter::new(100);
+        let mut lp = GameLoop::with_clock(10 * MS, ManualClock::default());
+        lp.run(&mut game).unwrap();
+
+        assert_eq!(game.updates, 100);
+        assert_eq!(game.frames, 99);
+        // the 100th update is due at 990ms, deadlines are absolute so nothing adds up
+        assert_eq!(lp.clock().now, 990 * MS);
+        assert_eq!(lp.stats().last_interval, 10 * MS);
+    }
+
+    #[test]
+    fn catch_up_is_bounded() {
+        let mut game = Counter::new(u32::MAX);
+        let mut lp = GameLoop::with_clock(10 * MS, ManualClock::default()).with_max_catch_up(3);
+        lp.tick(&mut game).unwrap();
+
+        // a 100ms stall, 11 updates are due but only 3 may run
+        lp.clock.now += 100 * MS;
+        lp.tick(&mut game).unwrap();
+        assert_eq!(game.updates, 4);
+        assert_eq!(lp.stats().skipped_updates, 8);
+        assert_eq!(lp.clock().now, 120 * MS);
+    }
+
+    #[test]
+    fn decoupled_render_rate() {
+        let mut game = Counter::new(60);
+        let mut lp =
+            GameLoop::with_clock(MS * 1000 / 60, ManualClock::default()).with_render_rate(20);
+        lp.run(&mut game).unwrap();
+        assert_eq!(game.frames, 20);
+        assert_eq!(lp.stats().late_frames, 0);
+    }
+
+    /// asks for a longer step after every other update
+    struct Uneven(u32);
+
+    impl Game for Uneven {
+        type Error = Infallible;
+
+        fn update(&mut self, _step: Duration) -> Result<Control, Infallible> {
+            self.0 += 1;
+            Ok(if self.0 == 4 {
+                Control::Stop
+            } else {
+                Control::Continue
+            })
+        }
+
+        fn next_step(&self) -> Option<Duration> {
+            (self.0 % 2 == 1).then_some(25 * MS)
+        }
+
+        fn render(&mut self, _alpha: f32, _stats: &LoopStats) -> Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn per_update_steps() {
+        let mut lp = GameLoop::with_clock(10 * MS, ManualClock::default());
+        lp.run(&mut Uneven(0)).unwrap();
+        // 25 after the 1st and 3rd update, 10 after the 2nd
+        assert_eq!(lp.clock().now, 60 * MS);
+        assert_eq!(lp.step(), 10 * MS);
+    }
+
+    #[test]
+    fn catch_up_keeps_the_game_step() {
+        struct Slow;
+        impl Game for Slow {
+            type Error = Infallible;
+
+            fn update(&mut self, _step: Duration) -> Result<Control, Infallible> {
+                Ok(Control::Continue)
+            }
+
+            fn next_step(&self) -> Option<Duration> {
+                Some(25 * MS)
+            }
+
+            fn render(&mut self, _alpha: f32, _stats: &LoopStats) -> Result<(), Infallible> {
+                Ok(())
+            }
+        }
+
+        let mut lp = GameLoop::with_clock(10 * MS, ManualClock::default()).with_max_catch_up(1);
+        lp.tick(&mut Slow).unwrap();
+        lp.clock.now += 100 * MS;
+        lp.tick(&mut Slow).unwrap();
+        // woken at 25ms and stalled until 125ms, the update at 25ms runs and those at
+        // 50, 75, 100 and 125ms are skipped
+        assert_eq!(lp.stats().skipped_updates, 4);
+        assert_eq!(lp.clock().now, 150 * MS);
+    }
+
+    #[test]
+    fn paused_skips_updates() {
+        struct Pausing(u32);
+        impl Game for Pausing {
+            type Error = Infallible;
+
+            fn poll(&mut self) -> Control {
+                self.0 += 1;
+                match self.0 {
+                    2 => Control::Pause,
+                    5 => Control::Resume,
+                    8 => Control::Stop,
+                    _ => Control::Continue,
+                }
+            }
+
+            fn update(&mut self, _step: Duration) -> Result<Control, Infallible> {
+                Ok(Control::Continue)
+            }
+
+            fn render(&mut self, _alpha: f32, _stats: &LoopStats) -> Result<(), Infallible> {
+                Ok(())
+            }
+        }
+
+        let mut lp = GameLoop::with_clock(10 * MS, ManualClock::default());
+        lp.run(&mut Pausing(0)).unwrap();
+        // iterations 2, 3 and 4 were paused, 8 stopped before updating
+        assert_eq!(lp.stats().updates, 4);
+        assert_eq!(lp.stats().frames, 7);
+        assert!(!lp.is_paused());
+    }
+}
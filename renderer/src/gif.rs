@@ -0,0 +1,425 @@
+//! GIF87a and GIF89a decoding into whole canvas frames.
+//!
+//! Every frame is drawn onto the canvas left by the previous one after that one
+//! was disposed of, transparent pixels keep what is below them. Areas restored to
+//! the background take the background color of the global palette.
+
+use std::{
+    io::{self, Read},
+    time::Duration,
+};
+
+use crate::{
+    image::{Image, Rgb},
+    types::{Rect, Size},
+};
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// largest code of the LZW dictionary, GIF codes are at most 12 bits
+const MAX_CODES: usize = 4096;
+const NO_PREFIX: u16 = u16::MAX;
+
+/// what happens to a frame's area before the next frame is drawn
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+enum Disposal {
+    #[default]
+    Keep,
+    Background,
+    Previous,
+}
+
+/// graphic control extension, applies to the next image only
+#[derive(Debug, Clone, Copy, Default)]
+struct Control {
+    delay: Option<Duration>,
+    transparent: Option<u8>,
+    disposal: Disposal,
+}
+
+pub struct Frame {
+    pub image: Image<Rgb>,
+    /// how long the frame stays, `None` when the file leaves it to the player
+    pub delay: Option<Duration>,
+}
+
+pub struct Decoder<R: Read> {
+    input: R,
+    size: Size,
+    global: Option<Vec<Rgb>>,
+    background: Rgb,
+    canvas: Vec<Rgb>,
+    /// area of the last frame with what it is disposed to
+    dispose: Option<(Rect, Vec<Rgb>)>,
+    repeat: Option<u16>,
+    done: bool,
+}
+
+impl<R: Read> Decoder<R> {
+    pub fn new(mut input: R) -> io::Result<Self> {
+        let mut head = [0u8; 13];
+        input.read_exact(&mut head)?;
+        if !matches!(&head[..6], b"GIF87a" | b"GIF89a") {
+            return Err(invalid("not a gif image"));
+        }
+
+        let size = Size::new(
+            u16::from_le_bytes([head[6], head[7]]) as usize,
+            u16::from_le_bytes([head[8], head[9]]) as usize,
+        );
+        if size.flatten() == 0 {
+            return Err(invalid("gif canvas has no pixels"));
+        }
+        let global = match head[10] & 0x80 {
+            0 => None,
+            _ => Some(palette(&mut input, head[10])?),
+        };
+        let background = global
+            .as_ref()
+            .and_then(|g| g.get(head[11] as usize).copied())
+            .unwrap_or([0; 3]);
+
+        Ok(Self {
+            input,
+            size,
+            global,
+            background,
+            canvas: vec![background; size.flatten()],
+            dispose: None,
+            repeat: None,
+            done: false,
+        })
+    }
+
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// loop count of the animation, `Some(0)` loops forever, only known once the
+    /// extension carrying it was read, usually before the first frame
+    pub fn repeat(&self) -> Option<u16> {
+        self.repeat
+    }
+
+    /// `None` after the trailer
+    pub fn next_frame(&mut self) -> io::Result<Option<Frame>> {
+        let mut control = Control::default();
+        while !self.done {
+            match self.byte()? {
+                0x21 => {
+                    let label = self.byte()?;
+                    let data = self.sub_blocks()?;
+                    match label {
+                        0xF9 if data.len() >= 4 => {
+                            let cs = u16::from_le_bytes([data[1], data[2]]) as u64;
+                            control = Control {
+                                delay: (cs > 0).then(|| Duration::from_millis(cs * 10)),
+                                transparent: (data[0] & 1 == 1).then_some(data[3]),
+                                disposal: match data[0] >> 2 & 7 {
+                                    2 => Disposal::Background,
+                                    3 => Disposal::Previous,
+                                    _ => Disposal::Keep,
+                                },
+                            };
+                        }
+                        0xFF if data.starts_with(b"NETSCAPE2.0") && data.len() >= 14 => {
+                            self.repeat = Some(u16::from_le_bytes([data[12], data[13]]));
+                        }
+                        _ => {}
+                    }
+                }
+                0x2C => {
+                    self.draw(control)?;
+                    return Ok(Some(Frame {
+                        image: Image::new(self.size, self.canvas.clone()),
+                        delay: control.delay,
+                    }));
+                }
+                0x3B => self.done = true,
+                _ => return Err(invalid("unknown gif block")),
+            }
+        }
+        Ok(None)
+    }
+
+    /// reads an image descriptor and its data onto the disposed canvas
+    fn draw(&mut self, control: Control) -> io::Result<()> {
+        let mut desc = [0u8; 9];
+        self.input.read_exact(&mut desc)?;
+        let word = |i: usize| u16::from_le_bytes([desc[i], desc[i + 1]]) as usize;
+        let rect = Rect::new(word(0), word(2), word(4), word(6));
+        let local = match desc[8] & 0x80 {
+            0 => None,
+            _ => Some(palette(&mut self.input, desc[8])?),
+        };
+        let interlaced = desc[8] & 0x40 != 0;
+
+        let min_size = self.byte()?;
+        let data = self.sub_blocks()?;
+        let indices = lzw(&data, min_size, rect.w * rect.h)?;
+        if local.is_none() && self.global.is_none() {
+            return Err(invalid("gif image without a palette"));
+        }
+
+        if let Some((area, pixels)) = self.dispose.take() {
+            self.restore(area, &pixels);
+        }
+        self.dispose = match control.disposal {
+            Disposal::Keep => None,
+            Disposal::Background => Some((rect, vec![self.background; rect.w * rect.h])),
+            Disposal::Previous => Some((rect, self.pixels_of(rect))),
+        };
+
+        let colors = local.as_ref().or(self.global.as_ref()).unwrap();
+        let rows = row_order(rect.h, interlaced);
+        for (i, &idx) in indices.iter().enumerate() {
+            let (x, y) = (rect.x + i % rect.w, rect.y + rows[i / rect.w]);
+            if Some(idx) == control.transparent || x >= self.size.w || y >= self.size.h {
+                continue;
+            }
+            if let Some(&color) = colors.get(idx as usize) {
+                self.canvas[y * self.size.w + x] = color;
+            }
+        }
+        Ok(())
+    }
+
+    /// canvas pixels of `rect`, row by row, `restore` writes them back
+    fn pixels_of(&self, rect: Rect) -> Vec<Rgb> {
+        let mut pixels = Vec::with_capacity(rect.w * rect.h);
+        for y in rect.y..rect.y + rect.h {
+            for x in rect.x..rect.x + rect.w {
+                let inside = x < self.size.w && y < self.size.h;
+                pixels.push(if inside {
+                    self.canvas[y * self.size.w + x]
+                } else {
+                    self.background
+                });
+            }
+        }
+        pixels
+    }
+
+    fn restore(&mut self, rect: Rect, pixels: &[Rgb]) {
+        for (i, &color) in pixels.iter().enumerate() {
+            let (x, y) = (rect.x + i % rect.w, rect.y + i / rect.w);
+            if x < self.size.w && y < self.size.h {
+                self.canvas[y * self.size.w + x] = color;
+            }
+        }
+    }
+
+    fn byte(&mut self) -> io::Result<u8> {
+        let mut b = [0u8];
+        self.input.read_exact(&mut b)?;
+        Ok(b[0])
+    }
+
+    /// data sub-blocks up to their terminator, joined
+    fn sub_blocks(&mut self) -> io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        loop {
+            let len = self.byte()? as usize;
+            if len == 0 {
+                return Ok(data);
+            }
+            let start = data.len();
+            data.resize(start + len, 0);
+            self.input.read_exact(&mut data[start..])?;
+        }
+    }
+}
+
+impl<R: Read> Iterator for Decoder<R> {
+    type Item = io::Result<Frame>;
+
+    fn next(&mut self) -> Option<io::Result<Frame>> {
+        self.next_frame().transpose()
+    }
+}
+
+/// color table announced by the low bits of `flags`
+fn palette<R: Read>(input: &mut R, flags: u8) -> io::Result<Vec<Rgb>> {
+    let mut raw = vec![0u8; 3 << ((flags & 7) + 1)];
+    input.read_exact(&mut raw)?;
+    Ok(raw.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect())
+}
+
+/// canvas row of each stored row, interlaced images store every 8th row from 0,
+/// every 8th from 4, every 4th from 2 and then every 2nd from 1
+fn row_order(h: usize, interlaced: bool) -> Vec<usize> {
+    if !interlaced {
+        return (0..h).collect();
+    }
+    [(0, 8), (4, 8), (2, 4), (1, 2)]
+        .into_iter()
+        .flat_map(|(start, step)| (start..h).step_by(step))
+        .collect()
+}
+
+/// color indices of up to `len` pixels, data ending early gives fewer
+fn lzw(data: &[u8], min_size: u8, len: usize) -> io::Result<Vec<u8>> {
+    if !(1..=11).contains(&min_size) {
+        return Err(invalid("gif code size out of range"));
+    }
+    let clear = 1u16 << min_size;
+    let end = clear + 1;
+
+    // every code is its prefix code plus one byte, roots have no prefix
+    let mut prefix = vec![NO_PREFIX; MAX_CODES];
+    let mut suffix: Vec<u8> = (0..MAX_CODES).map(|c| c as u8).collect();
+    let mut size = min_size + 1;
+    let mut next = end + 1;
+    let mut prev: Option<u16> = None;
+
+    let mut out = Vec::with_capacity(len);
+    let mut word = Vec::new();
+    let (mut acc, mut bits, mut pos) = (0u32, 0u8, 0);
+
+    while out.len() < len {
+        while bits < size && pos < data.len() {
+            acc |= (data[pos] as u32) << bits;
+            bits += 8;
+            pos += 1;
+        }
+        if bits < size {
+            break;
+        }
+        let code = (acc & ((1 << size) - 1)) as u16;
+        acc >>= size;
+        bits -= size;
+
+        if code == clear {
+            size = min_size + 1;
+            next = end + 1;
+            prev = None;
+            continue;
+        }
+        if code == end {
+            break;
+        }
+
+        let Some(p) = prev else {
+            if code >= clear {
+                return Err(invalid("gif data starts with an unknown code"));
+            }
+            out.push(code as u8);
+            prev = Some(code);
+            continue;
+        };
+
+        // the code right after the last one is the previous word plus its own first byte
+        let known = match code {
+            c if c < next => c,
+            c if c == next => p,
+            _ => return Err(invalid("gif data refers to an unknown code")),
+        };
+        word.clear();
+        let mut c = known;
+        while c != NO_PREFIX {
+            word.push(suffix[c as usize]);
+            c = prefix[c as usize];
+        }
+        word.reverse();
+        let first = word[0];
+        if code == next {
+            word.push(first);
+        }
+        out.extend_from_slice(&word);
+
+        // a full dictionary stays as it is until the next clear code
+        if (next as usize) < MAX_CODES {
+            prefix[next as usize] = p;
+            suffix[next as usize] = first;
+            next += 1;
+            if next == 1 << size && size < 12 {
+                size += 1;
+            }
+        }
+        prev = Some(code);
+    }
+
+    out.truncate(len);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io, time::Duration};
+
+    use super::{Decoder, row_order};
+
+    const BLACK: [u8; 3] = [0, 0, 0];
+    const WHITE: [u8; 3] = [255, 255, 255];
+    const RED: [u8; 3] = [255, 0, 0];
+    const BLUE: [u8; 3] = [0, 0, 255];
+    const GREEN: [u8; 3] = [0, 255, 0];
+
+    #[test]
+    fn still_checkerboard() {
+        let mut gif = Decoder::new(&include_bytes!("../fixtures/still.gif")[..]).unwrap();
+        let frame = gif.next_frame().unwrap().unwrap();
+        assert_eq!(frame.delay, None);
+        for (i, px) in frame.image.pixels.iter().enumerate() {
+            let on = (i % 4 + i / 4) % 2 == 1;
+            assert_eq!(*px, if on { WHITE } else { BLACK });
+        }
+        assert!(gif.next_frame().unwrap().is_none());
+        assert_eq!(gif.repeat(), None);
+    }
+
+    #[test]
+    fn animation_disposal_and_transparency() {
+        let gif = Decoder::new(&include_bytes!("../fixtures/anim.gif")[..]).unwrap();
+        let frames: Vec<_> = gif.collect::<io::Result<_>>().unwrap();
+        assert_eq!(frames.len(), 4);
+
+        let delays: Vec<_> = frames.iter().map(|f| f.delay).collect();
+        let ms = |ms| Some(Duration::from_millis(ms));
+        assert_eq!(delays, [ms(100), ms(200), ms(50), None]);
+
+        let px = |f: usize, x: usize, y: usize| *frames[f].image.get(x, y);
+        assert!(frames[0].image.pixels.iter().all(|&p| p == WHITE));
+        // transparent pixels of the local palette keep the white below
+        assert_eq!(
+            [px(1, 1, 1), px(1, 2, 1), px(1, 2, 2)],
+            [GREEN, WHITE, GREEN]
+        );
+        // the green square went back to the background before the red one
+        assert_eq!([px(2, 1, 1), px(2, 2, 1), px(2, 3, 1)], [BLACK, BLACK, RED]);
+        // the red square was put back to white, the interlaced column is in order
+        assert_eq!([px(3, 3, 1), px(3, 3, 2)], [WHITE, WHITE]);
+        let column: Vec<_> = (0..4).map(|y| px(3, 4, y)).collect();
+        assert_eq!(column, [RED, BLUE, BLACK, RED]);
+        assert_eq!(px(3, 1, 2), BLACK);
+    }
+
+    #[test]
+    fn full_dictionary() {
+        let mut gif = Decoder::new(&include_bytes!("../fixtures/noise.gif")[..]).unwrap();
+        let frame = gif.next_frame().unwrap().unwrap();
+
+        // the generator's LCG
+        let mut v: u32 = 1;
+        for y in 0..96 {
+            for x in 0..128 {
+                v = v.wrapping_mul(1103515245).wrapping_add(12345) & 0x7FFF_FFFF;
+                let idx = if (x / 8 + y) % 7 != 0 {
+                    v >> 16 & 15
+                } else {
+                    0
+                };
+                assert_eq!(*frame.image.get(x, y), [idx as u8 * 17; 3], "at {x},{y}");
+            }
+        }
+    }
+
+    #[test]
+    fn interlaced_rows() {
+        assert_eq!(row_order(4, true), [0, 2, 1, 3]);
+        assert_eq!(row_order(10, true), [0, 8, 4, 2, 6, 1, 3, 5, 7, 9]);
+        assert_eq!(row_order(3, false), [0, 1, 2]);
+    }
+}
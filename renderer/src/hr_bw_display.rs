@@ -1,7 +1,13 @@
-use std::io::{self, Write};
+use std::{
+    io::{self, Stdout, Write},
+    mem,
+    time::Duration,
+};
 
 use crate::{
     chunk_iter::{Collector, ToChunks},
+    error::Error,
+    output::FrameWriter,
     traits::RenderTarget,
     types::Size,
 };
@@ -30,6 +36,7 @@ pub const DOTS_TABLE: [char; 256] = [
     '⣻', '⠟', '⢟', '⡟', '⣟', '⠿', '⢿', '⡿', '⣿',
 ];
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum Res {
     Low,
     High,
@@ -37,7 +44,8 @@ pub enum Res {
 }
 
 impl Res {
-    fn to_size(&self) -> Size {
+    /// pixels drawn by a single glyph
+    pub fn to_size(&self) -> Size {
         match self {
             Self::High => Size { w: 2, h: 3 },
             Self::Low => Size { w: 1, h: 2 },
@@ -69,58 +77,182 @@ impl Collector<bool> for u8 {
     }
 }
 
-pub struct HighResBWScreen {
+/// turns frames into the text `HighResBWScreen` writes, away from the screen so
+/// frames can be rendered ahead on other threads
+#[derive(Debug, Clone, Copy)]
+pub struct Glyphs {
     w: usize,
     rw: usize,
     res: Res,
 }
 
-impl HighResBWScreen {
-    pub fn new(w: usize, res: Res) -> Self {
-        Self {
+impl Glyphs {
+    pub fn new(w: usize, res: Res) -> Result<Self, Error> {
+        if w == 0 {
+            return Err(Error::InvalidSize(Size::new(w, 0)));
+        }
+
+        Ok(Self {
             w,
             rw: w.div_ceil(res.to_size().w),
             res,
-        }
+        })
     }
-}
 
-impl RenderTarget<bool> for HighResBWScreen {
-    type Error = io::Error;
-
-    fn init(&self) -> Result<(), Self::Error> {
-        print!("\x1B[?1049h");
-        print!("\x1B[?25l");
-        print!("\x1B[2J\x1B[H");
-        io::stdout().flush()
-    }
-
-    fn exit(&self) -> Result<(), Self::Error> {
-        print!("\x1B[?1049l");
-        print!("\x1b[?25h");
-        io::stdout().flush()
-    }
-
-    fn draw<I>(&mut self, items: I) -> Result<(), Self::Error>
+    /// the whole frame from the top left corner, rows of `w` pixels
+    pub fn render<I>(&self, items: I) -> Result<String, Error>
     where
         I: Iterator<Item = bool>,
     {
         let size = self.res.to_size();
 
+        let mut count = 0;
         let mut scaled = items
+            .inspect(|_| count += 1)
             .to_chunks::<u8>(self.w, size.w, size.h)
             .map(|x| self.res.render(x));
 
+        let mut text = String::from("\x1B[H");
         loop {
-            let line = scaled.by_ref().take(self.rw).collect::<String>();
+            let start = text.len();
+            text.push_str("\n\r");
+            text.extend(scaled.by_ref().take(self.rw));
 
-            if line.is_empty() {
+            if text.len() == start + 2 {
+                text.truncate(start);
                 break;
             }
+        }
 
-            print!("\n\r{}", line);
+        if count % self.w != 0 {
+            return Err(Error::SizeMismatch {
+                expected: Size::new(self.w, count.div_ceil(self.w)),
+                found: Size::from_flat(count, self.w),
+            });
         }
+        Ok(text)
+    }
+}
+
+pub struct HighResBWScreen<W: Write = Stdout> {
+    glyphs: Glyphs,
+    out: FrameWriter<W>,
+    /// between `init` and `exit`, the terminal is restored once when dropped
+    active: bool,
+}
+
+impl HighResBWScreen {
+    pub fn new(w: usize, res: Res) -> Result<Self, Error> {
+        Self::with_output(w, res, io::stdout())
+    }
+}
+
+impl<W: Write> HighResBWScreen<W> {
+    pub fn with_output(w: usize, res: Res, out: W) -> Result<Self, Error> {
+        Ok(Self {
+            glyphs: Glyphs::new(w, res)?,
+            out: FrameWriter::new(out),
+            active: false,
+        })
+    }
+
+    /// wrap every frame in a synchronized update, see `FrameWriter`
+    pub fn with_sync_output(mut self, sync: bool) -> Self {
+        self.out.set_sync(sync);
+        self
+    }
+
+    /// time the last frame took to reach the output, part of the `draw` time
+    pub fn last_write(&self) -> Duration {
+        self.out.last_write()
+    }
+
+    /// renders frames the way this screen draws them
+    pub fn glyphs(&self) -> Glyphs {
+        self.glyphs
+    }
+
+    /// draws a frame rendered by `glyphs` ahead of time
+    pub fn write_rendered(&mut self, text: &str) -> Result<(), Error> {
+        self.out.write_all(text.as_bytes())?;
+        Ok(self.out.finish()?)
+    }
+}
+
+impl<W: Write> RenderTarget<bool> for HighResBWScreen<W> {
+    type Error = Error;
+
+    fn init(&mut self) -> Result<(), Self::Error> {
+        self.active = true;
+        write!(self.out, "\x1B[?1049h")?;
+        write!(self.out, "\x1B[?25l")?;
+        write!(self.out, "\x1B[2J\x1B[H")?;
+        Ok(self.out.finish()?)
+    }
+
+    fn exit(&mut self) -> Result<(), Self::Error> {
+        if !mem::take(&mut self.active) {
+            return Ok(());
+        }
+        write!(self.out, "\x1B[?1049l")?;
+        write!(self.out, "\x1b[?25h")?;
+        Ok(self.out.finish()?)
+    }
+
+    fn check_size(&self, size: Size) -> Result<(), Error> {
+        if size.w != self.glyphs.w {
+            return Err(Error::SizeMismatch {
+                expected: Size::new(self.glyphs.w, size.h),
+                found: size,
+            });
+        }
+        Ok(())
+    }
+
+    fn draw<I>(&mut self, items: I) -> Result<(), Self::Error>
+    where
+        I: Iterator<Item = bool>,
+    {
+        let text = self.glyphs.render(items)?;
+        self.write_rendered(&text)
+    }
+}
+
+impl<W: Write> Drop for HighResBWScreen<W> {
+    fn drop(&mut self) {
+        let _ = self.exit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Glyphs, HighResBWScreen, Res};
+    use crate::traits::RenderTarget;
+
+    #[test]
+    fn rendered_ahead_like_drawn() {
+        let pixels = [true, false, false, true, true, true, false, false];
+        let glyphs = Glyphs::new(2, Res::Low).unwrap();
+        let text = glyphs.render(pixels.into_iter()).unwrap();
+        assert_eq!(text, "\x1B[H\n\r▀▄\n\r▀▀");
+
+        let mut screen = HighResBWScreen::with_output(2, Res::Low, Vec::new()).unwrap();
+        screen.draw(pixels.into_iter()).unwrap();
+        assert_eq!(screen.out.get_ref(), text.as_bytes());
+
+        assert!(glyphs.render(pixels[..3].iter().copied()).is_err());
+    }
+
+    #[test]
+    fn restores_the_terminal_once() {
+        let mut screen = HighResBWScreen::with_output(2, Res::Low, Vec::new()).unwrap();
+        screen.exit().unwrap();
+        assert!(screen.out.get_ref().is_empty());
 
-        io::stdout().flush()
+        screen.init().unwrap();
+        screen.exit().unwrap();
+        let written = screen.out.get_ref().len();
+        screen.exit().unwrap();
+        assert_eq!(screen.out.get_ref().len(), written);
     }
 }
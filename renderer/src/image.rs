@@ -0,0 +1,66 @@
+use crate::{traits::SizedRenderble, types::Size};
+
+pub type Rgb = [u8; 3];
+
+/// Rec. 601 luma of a color
+pub fn luma([r, g, b]: Rgb) -> u8 {
+    ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114 + 500) / 1000) as u8
+}
+
+/// row-major pixel buffer
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Image<T> {
+    pub size: Size,
+    pub pixels: Vec<T>,
+}
+
+impl<T> Image<T> {
+    /// `pixels` has to hold exactly `size.flatten()` items
+    pub fn new(size: Size, pixels: Vec<T>) -> Self {
+        assert_eq!(
+            size.flatten(),
+            pixels.len(),
+            "pixel count does not match size"
+        );
+        Self { size, pixels }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> &T {
+        &self.pixels[y * self.size.w + x]
+    }
+
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[T]> {
+        self.pixels.chunks(self.size.w.max(1))
+    }
+
+    pub fn map<U, F>(&self, f: F) -> Image<U>
+    where
+        F: FnMut(&T) -> U,
+    {
+        Image {
+            size: self.size,
+            pixels: self.pixels.iter().map(f).collect(),
+        }
+    }
+}
+
+impl<T: Clone> Image<T> {
+    pub fn filled(size: Size, v: T) -> Self {
+        Self {
+            size,
+            pixels: vec![v; size.flatten()],
+        }
+    }
+}
+
+impl<T: Copy> SizedRenderble for Image<T> {
+    type Primitive = T;
+
+    fn size(&self) -> Size {
+        self.size
+    }
+
+    fn render_rows(&self) -> impl Iterator<Item = impl Iterator<Item = Self::Primitive>> {
+        self.rows().map(|row| row.iter().copied())
+    }
+}
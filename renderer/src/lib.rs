@@ -1,6 +1,22 @@
+pub mod anim;
 pub mod bit_chunk_iter;
+pub mod caps;
+pub mod chart;
 pub mod chunk_iter;
+pub mod error;
+pub mod filter;
+pub mod game_loop;
+pub mod gif;
 pub mod hr_bw_display;
+pub mod image;
+pub mod output;
+pub mod pnm;
+pub mod profile;
+pub mod scale;
 pub mod term_display;
+pub mod text;
 pub mod traits;
+pub mod transform;
 pub mod types;
+
+pub use error::Error;
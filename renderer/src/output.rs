@@ -0,0 +1,126 @@
+use std::{
+    io::{self, Stdout, Write},
+    time::{Duration, Instant},
+};
+
+/// begin / end synchronized update (DEC private mode 2026)
+const SYNC_BEGIN: &[u8] = b"\x1B[?2026h";
+const SYNC_END: &[u8] = b"\x1B[?2026l";
+
+/// assembles a whole frame in a reusable buffer and hands it to the writer in one `write_all`,
+/// optionally wrapped in a synchronized update so the terminal never shows it half drawn
+pub struct FrameWriter<W: Write = Stdout> {
+    buf: Vec<u8>,
+    out: W,
+    sync: bool,
+    last_write: Duration,
+}
+
+impl FrameWriter {
+    pub fn stdout() -> Self {
+        Self::new(io::stdout())
+    }
+}
+
+impl<W: Write> FrameWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            buf: Vec::new(),
+            out,
+            sync: false,
+            last_write: Duration::ZERO,
+        }
+    }
+
+    pub fn set_sync(&mut self, sync: bool) {
+        self.sync = sync;
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.out
+    }
+
+    /// time the last `finish` spent handing the frame to the writer
+    pub fn last_write(&self) -> Duration {
+        self.last_write
+    }
+
+    /// drops everything buffered since the last `finish`
+    pub fn discard(&mut self) {
+        self.buf.clear();
+    }
+
+    /// writes out everything buffered since the last `finish` as a single frame
+    pub fn finish(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        if self.sync {
+            self.buf.extend_from_slice(SYNC_END);
+        }
+        let start = Instant::now();
+        let res = self.out.write_all(&self.buf).and_then(|_| self.out.flush());
+        self.last_write = start.elapsed();
+        self.buf.clear();
+        res
+    }
+}
+
+impl<W: Write> Write for FrameWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.buf.is_empty() && self.sync {
+            self.buf.extend_from_slice(SYNC_BEGIN);
+        }
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::{self, Write};
+
+    use super::FrameWriter;
+
+    /// records everything written together with the number of `write` calls
+    #[derive(Default)]
+    pub struct CountingWriter {
+        pub data: Vec<u8>,
+        pub writes: usize,
+    }
+
+    impl Write for CountingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.writes += 1;
+            self.data.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn frame_is_one_write() {
+        let mut out = FrameWriter::new(CountingWriter::default());
+        out.set_sync(true);
+
+        for i in 0..100 {
+            write!(out, "\n\rline {}", i).unwrap();
+        }
+        out.finish().unwrap();
+
+        let counter = out.get_ref();
+        assert_eq!(counter.writes, 1);
+        assert!(counter.data.starts_with(b"\x1B[?2026h\n\rline 0"));
+        assert!(counter.data.ends_with(b"line 99\x1B[?2026l"));
+
+        out.finish().unwrap();
+        assert_eq!(out.get_ref().writes, 1);
+    }
+}
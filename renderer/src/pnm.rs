@@ -0,0 +1,233 @@
+//! Netpbm images, `P1` to `P6` in both their ASCII and binary forms.
+//!
+//! Bitmaps follow the picture rather than the ink: a PBM `1` is black and
+//! decodes to `false`, so they draw like any other 1-bit frame.
+//! Images are between 1 and 16384 pixels on each side.
+
+use std::io::{self, BufRead};
+
+use crate::{
+    image::{Image, Rgb, luma},
+    types::Size,
+};
+
+/// widest and tallest image read, larger headers are taken for corrupt
+const MAX_SIDE: u32 = 16384;
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// decoded image in the depth of its file, samples scaled to `0..=255`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Pnm {
+    Bits(Image<bool>),
+    Gray(Image<u8>),
+    Rgb(Image<Rgb>),
+}
+
+impl Pnm {
+    pub fn size(&self) -> Size {
+        match self {
+            Self::Bits(img) => img.size,
+            Self::Gray(img) => img.size,
+            Self::Rgb(img) => img.size,
+        }
+    }
+
+    /// color images are reduced to their luma
+    pub fn gray(&self) -> Image<u8> {
+        match self {
+            Self::Bits(img) => img.map(|&b| if b { u8::MAX } else { 0 }),
+            Self::Gray(img) => img.clone(),
+            Self::Rgb(img) => img.map(|&c| luma(c)),
+        }
+    }
+
+    pub fn rgb(&self) -> Image<Rgb> {
+        match self {
+            Self::Rgb(img) => img.clone(),
+            other => other.gray().map(|&v| [v; 3]),
+        }
+    }
+}
+
+fn peek<R: BufRead>(input: &mut R) -> io::Result<Option<u8>> {
+    Ok(input.fill_buf()?.first().copied())
+}
+
+fn byte<R: BufRead>(input: &mut R) -> io::Result<u8> {
+    let b = peek(input)?.ok_or_else(|| invalid("pnm image ends early"))?;
+    input.consume(1);
+    Ok(b)
+}
+
+/// whitespace and `#` comments up to the end of their line
+fn skip_space<R: BufRead>(input: &mut R) -> io::Result<()> {
+    while let Some(b) = peek(input)? {
+        match b {
+            b'#' => {
+                input.read_until(b'\n', &mut Vec::new())?;
+            }
+            b if b.is_ascii_whitespace() => input.consume(1),
+            _ => break,
+        }
+    }
+    Ok(())
+}
+
+fn number<R: BufRead>(input: &mut R) -> io::Result<u32> {
+    skip_space(input)?;
+    let mut n: Option<u32> = None;
+    while let Some(b @ b'0'..=b'9') = peek(input)? {
+        input.consume(1);
+        n = n
+            .unwrap_or(0)
+            .checked_mul(10)
+            .and_then(|n| n.checked_add((b - b'0') as u32));
+        if n.is_none() {
+            return Err(invalid("pnm number out of range"));
+        }
+    }
+    n.ok_or_else(|| invalid("pnm number expected"))
+}
+
+/// next image of the stream, `None` when it is used up, so concatenated images
+/// like those of `ffmpeg -f image2pipe` can be read one after another
+pub fn read<R: BufRead>(input: &mut R) -> io::Result<Option<Pnm>> {
+    skip_space(input)?;
+    if peek(input)?.is_none() {
+        return Ok(None);
+    }
+    let kind = match [byte(input)?, byte(input)?] {
+        [b'P', k @ b'1'..=b'6'] => k - b'0',
+        _ => return Err(invalid("not a pnm image")),
+    };
+
+    let (w, h) = (number(input)?, number(input)?);
+    if !(1..=MAX_SIDE).contains(&w) || !(1..=MAX_SIDE).contains(&h) {
+        return Err(invalid("pnm size out of range"));
+    }
+    let size = Size::new(w as usize, h as usize);
+    let max = match kind {
+        1 | 4 => 1,
+        _ => number(input)?,
+    };
+    if !(1..=u16::MAX as u32).contains(&max) {
+        return Err(invalid("pnm maxval out of range"));
+    }
+    if kind >= 4 && !byte(input)?.is_ascii_whitespace() {
+        return Err(invalid("pnm header is not followed by whitespace"));
+    }
+
+    let scale = |v: u32| (v.min(max) * 255 + max / 2) / max;
+    let sample = |input: &mut R| -> io::Result<u8> {
+        let v = match kind {
+            1..=3 => number(input)?,
+            _ if max > 255 => (byte(input)? as u32) << 8 | byte(input)? as u32,
+            _ => byte(input)? as u32,
+        };
+        Ok(scale(v) as u8)
+    };
+    let n = size.flatten();
+
+    let img = match kind {
+        1 => {
+            let mut pixels = Vec::new();
+            while pixels.len() < n {
+                skip_space(input)?;
+                match byte(input)? {
+                    b @ (b'0' | b'1') => pixels.push(b == b'0'),
+                    _ => return Err(invalid("pbm pixel is neither 0 nor 1")),
+                }
+            }
+            Pnm::Bits(Image::new(size, pixels))
+        }
+        4 => {
+            let mut row = vec![0u8; size.w.div_ceil(8)];
+            let mut pixels = Vec::new();
+            for _ in 0..size.h {
+                input.read_exact(&mut row)?;
+                pixels.extend((0..size.w).map(|x| row[x / 8] >> (7 - x % 8) & 1 == 0));
+            }
+            Pnm::Bits(Image::new(size, pixels))
+        }
+        2 | 5 => Pnm::Gray(Image::new(
+            size,
+            (0..n).map(|_| sample(input)).collect::<io::Result<_>>()?,
+        )),
+        _ => Pnm::Rgb(Image::new(
+            size,
+            (0..n)
+                .map(|_| Ok([sample(input)?, sample(input)?, sample(input)?]))
+                .collect::<io::Result<_>>()?,
+        )),
+    };
+    Ok(Some(img))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::{Pnm, read};
+    use crate::{image::Image, types::Size};
+
+    fn decode(data: &[u8]) -> Pnm {
+        read(&mut &data[..]).unwrap().unwrap()
+    }
+
+    #[test]
+    fn ascii_and_binary_agree() {
+        let p1 = decode(b"P1\n# comment\n3 2\n1 0 1\n010");
+        let p4 = decode(b"P4 3 2\n\xA0\x40");
+        assert_eq!(p1, p4);
+        assert_eq!(
+            p1,
+            Pnm::Bits(Image::new(
+                Size::new(3, 2),
+                vec![false, true, false, true, false, true]
+            ))
+        );
+
+        let p2 = decode(b"P2 2 1 15 0 15");
+        let p5 = decode(b"P5 2 1 15\n\x00\x0F");
+        assert_eq!(p2, p5);
+        assert_eq!(p2.gray().pixels, [0, 255]);
+
+        let p3 = decode(b"P3 1 1 255\n255 0 0");
+        let p6 = decode(b"P6 1 1 255\n\xFF\x00\x00");
+        assert_eq!(p3, p6);
+        assert_eq!(p6.gray().pixels, [76]);
+    }
+
+    #[test]
+    fn wide_samples_and_streams() {
+        let p5 = decode(b"P5 1 1 65535\n\x80\x00");
+        assert_eq!(p5.gray().pixels, [128]);
+
+        let mut stream: &[u8] = b"P5 1 1 255\n\x10P5 1 1 255\n\x20\n";
+        let first = read(&mut stream).unwrap().unwrap();
+        let second = read(&mut stream).unwrap().unwrap();
+        assert_eq!(
+            (first.gray().pixels, second.gray().pixels),
+            (vec![16], vec![32])
+        );
+        assert!(read(&mut stream).unwrap().is_none());
+
+        assert!(read(&mut &b"P5 2 2 255\n\x00"[..]).is_err());
+        assert!(read(&mut &b"P7 1 1"[..]).is_err());
+    }
+
+    #[test]
+    fn sizes_are_checked() {
+        for header in [
+            &b"P5 4294967295 4294967295 255\n"[..],
+            b"P5 0 0 255\n",
+            b"P4 1 0\n",
+        ] {
+            let err = read(&mut &header[..]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+/// handle to a stage registered on a `Profiler`
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct StageId(usize);
+
+/// statistics of one series of timings
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct Summary {
+    pub min: Duration,
+    pub avg: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl Summary {
+    /// all zero for an empty series
+    pub fn of(samples: &[Duration]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        // nearest rank
+        let rank = |p: usize| sorted[(sorted.len() * p).div_ceil(100).max(1) - 1];
+        Self {
+            min: sorted[0],
+            avg: sorted.iter().sum::<Duration>() / sorted.len() as u32,
+            p95: rank(95),
+            p99: rank(99),
+            max: sorted[sorted.len() - 1],
+        }
+    }
+}
+
+/// per frame timings of named stages plus the interval between frames
+#[derive(Debug, Clone, Default)]
+pub struct Profiler {
+    stages: Vec<&'static str>,
+    /// one row per finished frame, a column per stage
+    frames: Vec<Vec<Duration>>,
+    intervals: Vec<Duration>,
+    current: Vec<Duration>,
+    last_frame: Option<Instant>,
+    dropped: u64,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// registers a stage, frames finished before keep zero for it
+    pub fn stage(&mut self, name: &'static str) -> StageId {
+        self.stages.push(name);
+        self.current.push(Duration::ZERO);
+        StageId(self.stages.len() - 1)
+    }
+
+    /// adds time measured elsewhere, e.g. on another thread, to the current frame
+    pub fn record(&mut self, stage: StageId, time: Duration) {
+        self.current[stage.0] += time;
+    }
+
+    pub fn time<T, F: FnOnce() -> T>(&mut self, stage: StageId, f: F) -> T {
+        let start = Instant::now();
+        let out = f();
+        self.record(stage, start.elapsed());
+        out
+    }
+
+    /// closes the current frame, the interval is measured from the previous call
+    pub fn frame(&mut self) {
+        let now = Instant::now();
+        let interval = self.last_frame.map(|last| now - last);
+        self.last_frame = Some(now);
+        self.finish_frame(interval);
+    }
+
+    fn finish_frame(&mut self, interval: Option<Duration>) {
+        let stages = self.current.len();
+        self.frames.push(std::mem::replace(
+            &mut self.current,
+            vec![Duration::ZERO; stages],
+        ));
+        self.intervals.extend(interval);
+    }
+
+    /// counts a frame that was produced but never shown
+    pub fn drop_frame(&mut self) {
+        self.dropped += 1;
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn intervals(&self) -> &[Duration] {
+        &self.intervals
+    }
+
+    pub fn stage_summary(&self, stage: StageId) -> Summary {
+        let samples: Vec<_> = self.frames.iter().map(|f| f[stage.0]).collect();
+        Summary::of(&samples)
+    }
+
+    pub fn interval_summary(&self) -> Summary {
+        Summary::of(&self.intervals)
+    }
+
+    /// standard deviation of the frame interval
+    pub fn jitter(&self) -> Duration {
+        if self.intervals.len() < 2 {
+            return Duration::ZERO;
+        }
+        let secs: Vec<f64> = self.intervals.iter().map(Duration::as_secs_f64).collect();
+        let mean = secs.iter().sum::<f64>() / secs.len() as f64;
+        let var = secs.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / secs.len() as f64;
+        Duration::from_secs_f64(var.sqrt())
+    }
+
+    /// single line with the last frame's stages, fits a status line
+    pub fn overlay(&self) -> String {
+        let mut out = String::new();
+        if let Some(last) = self.frames.last() {
+            for (name, time) in self.stages.iter().zip(last) {
+                out += &format!("{} {} ", name, fmt_ms(*time));
+            }
+        }
+        let iv = self.interval_summary();
+        out += &format!(
+            "| p99 {} jit {} drop {}",
+            fmt_ms(iv.p99),
+            fmt_ms(self.jitter()),
+            self.dropped
+        );
+        out
+    }
+
+    /// table with a row per stage and one for the frame interval
+    pub fn report(&self) -> Vec<String> {
+        let name_w = self
+            .stages
+            .iter()
+            .map(|s| s.len())
+            .chain(["interval".len()])
+            .max()
+            .unwrap_or(0);
+        let row = |name: &str, s: Summary| {
+            format!(
+                "{:<name_w$} {:>9} {:>9} {:>9} {:>9} {:>9}",
+                name,
+                fmt_ms(s.min),
+                fmt_ms(s.avg),
+                fmt_ms(s.p95),
+                fmt_ms(s.p99),
+                fmt_ms(s.max),
+            )
+        };
+
+        let mut rows = vec![format!(
+            "{:<name_w$} {:>9} {:>9} {:>9} {:>9} {:>9}",
+            "stage", "min", "avg", "p95", "p99", "max"
+        )];
+        for (i, name) in self.stages.iter().enumerate() {
+            rows.push(row(name, self.stage_summary(StageId(i))));
+        }
+        rows.push(row("interval", self.interval_summary()));
+        rows.push(format!(
+            "frames {}, dropped {}, jitter {}",
+            self.frames.len(),
+            self.dropped,
+            fmt_ms(self.jitter())
+        ));
+        rows
+    }
+
+    /// one line per frame, times in microseconds, the first frame has no interval
+    pub fn write_csv<W: Write>(&self, mut out: W) -> io::Result<()> {
+        write!(out, "frame,interval_us")?;
+        for name in &self.stages {
+            write!(out, ",{}_us", name)?;
+        }
+        writeln!(out)?;
+
+        // intervals start with the second frame when measured by `frame`
+        let offset = self.frames.len() - self.intervals.len();
+        for (i, stages) in self.frames.iter().enumerate() {
+            write!(out, "{},", i)?;
+            if let Some(iv) = i.checked_sub(offset).map(|j| self.intervals[j]) {
+                write!(out, "{}", iv.as_micros())?;
+            }
+            for time in stages {
+                write!(out, ",{}", time.as_micros())?;
+            }
+            writeln!(out)?;
+        }
+        out.flush()
+    }
+}
+
+fn fmt_ms(d: Duration) -> String {
+    format!("{:.2}ms", d.as_secs_f64() * 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Profiler, Summary};
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn summary_percentiles() {
+        let samples: Vec<_> = (1..=100).map(|i| i * MS).collect();
+        let s = Summary::of(&samples);
+        assert_eq!((s.min, s.max), (MS, 100 * MS));
+        assert_eq!(s.avg, Duration::from_micros(50_500));
+        assert_eq!((s.p95, s.p99), (95 * MS, 99 * MS));
+
+        assert_eq!(Summary::of(&[]), Summary::default());
+    }
+
+    #[test]
+    fn stages_and_jitter() {
+        let mut prof = Profiler::new();
+        let decode = prof.stage("decode");
+        let write = prof.stage("write");
+
+        for i in 0..4 {
+            prof.record(decode, 2 * MS);
+            prof.record(write, i * MS);
+            prof.finish_frame((i > 0).then_some(if i % 2 == 0 { 30 * MS } else { 40 * MS }));
+        }
+        prof.drop_frame();
+
+        assert_eq!(prof.stage_summary(decode).avg, 2 * MS);
+        assert_eq!(prof.stage_summary(write).max, 3 * MS);
+        assert_eq!(prof.interval_summary().min, 30 * MS);
+        // intervals 40, 30, 40
+        let jitter = prof.jitter().as_secs_f64();
+        assert!((jitter - 0.004714).abs() < 1e-5);
+        assert!(prof.overlay().starts_with("decode 2.00ms write 3.00ms |"));
+        assert!(prof.overlay().ends_with("drop 1"));
+        assert_eq!(prof.report().len(), 5);
+    }
+
+    #[test]
+    fn csv_rows() {
+        let mut prof = Profiler::new();
+        let render = prof.stage("render");
+        prof.record(render, MS);
+        prof.finish_frame(None);
+        prof.record(render, 2 * MS);
+        prof.finish_frame(Some(33 * MS));
+
+        let mut out = Vec::new();
+        prof.write_csv(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "frame,interval_us,render_us\n0,,1000\n1,33000,2000\n"
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{image::Image, types::Size};
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Resample {
+    Nearest,
+    /// area average, for bool frames a pixel is set when more than half of its area is
+    #[default]
+    Box,
+    Bilinear,
+}
+
+/// pixels that can be averaged, on a 0.0..=255.0 scale
+pub trait Sample: Copy {
+    fn to_f32(self) -> f32;
+    fn from_f32(v: f32) -> Self;
+}
+
+impl Sample for u8 {
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    fn from_f32(v: f32) -> Self {
+        v.round().clamp(0.0, 255.0) as u8
+    }
+}
+
+impl Sample for bool {
+    fn to_f32(self) -> f32 {
+        if self { 255.0 } else { 0.0 }
+    }
+
+    fn from_f32(v: f32) -> Self {
+        v > 127.5
+    }
+}
+
+/// size multiplied by `factor` (2.0 doubles, 0.5 halves), never smaller than 1x1
+pub fn scale_by(size: Size, factor: f32) -> Size {
+    let dim = |d: usize| ((d as f32 * factor).round() as usize).max(1);
+    Size::new(dim(size.w), dim(size.h))
+}
+
+/// largest size with the aspect ratio of `src` that fits into `bounds`
+pub fn fit(src: Size, bounds: Size) -> Size {
+    if src.flatten() == 0 {
+        return Size::new(0, 0);
+    }
+
+    let factor = (bounds.w as f32 / src.w as f32).min(bounds.h as f32 / src.h as f32);
+    let size = scale_by(src, factor);
+    Size::new(size.w.min(bounds.w), size.h.min(bounds.h))
+}
+
+pub fn resize<T: Sample>(img: &Image<T>, size: Size, filter: Resample) -> Image<T> {
+    if img.size == size {
+        return img.clone();
+    }
+    if img.size.flatten() == 0 || size.flatten() == 0 {
+        return Image::new(size, vec![T::from_f32(0.0); size.flatten()]);
+    }
+
+    let sx = img.size.w as f32 / size.w as f32;
+    let sy = img.size.h as f32 / size.h as f32;
+
+    let mut pixels = Vec::with_capacity(size.flatten());
+    for y in 0..size.h {
+        for x in 0..size.w {
+            pixels.push(match filter {
+                Resample::Nearest => nearest(img, x, y, sx, sy),
+                Resample::Box => area(img, x, y, sx, sy),
+                Resample::Bilinear => bilinear(img, x, y, sx, sy),
+            });
+        }
+    }
+    Image::new(size, pixels)
+}
+
+fn nearest<T: Sample>(img: &Image<T>, x: usize, y: usize, sx: f32, sy: f32) -> T {
+    let src_x = (((x as f32 + 0.5) * sx) as usize).min(img.size.w - 1);
+    let src_y = (((y as f32 + 0.5) * sy) as usize).min(img.size.h - 1);
+    *img.get(src_x, src_y)
+}
+
+/// average of the source area covered by the destination pixel, weighted by coverage
+fn area<T: Sample>(img: &Image<T>, x: usize, y: usize, sx: f32, sy: f32) -> T {
+    let (x0, x1) = (x as f32 * sx, (x + 1) as f32 * sx);
+    let (y0, y1) = (y as f32 * sy, (y + 1) as f32 * sy);
+
+    let mut sum = 0.0;
+    let mut weight = 0.0;
+    for src_y in y0 as usize..(y1.ceil() as usize).min(img.size.h) {
+        let wy = (y1.min(src_y as f32 + 1.0) - y0.max(src_y as f32)).max(0.0);
+        for src_x in x0 as usize..(x1.ceil() as usize).min(img.size.w) {
+            let wx = (x1.min(src_x as f32 + 1.0) - x0.max(src_x as f32)).max(0.0);
+            sum += img.get(src_x, src_y).to_f32() * wx * wy;
+            weight += wx * wy;
+        }
+    }
+    T::from_f32(sum / weight)
+}
+
+fn bilinear<T: Sample>(img: &Image<T>, x: usize, y: usize, sx: f32, sy: f32) -> T {
+    let fx = ((x as f32 + 0.5) * sx - 0.5).clamp(0.0, (img.size.w - 1) as f32);
+    let fy = ((y as f32 + 0.5) * sy - 0.5).clamp(0.0, (img.size.h - 1) as f32);
+
+    let (x0, y0) = (fx as usize, fy as usize);
+    let (x1, y1) = ((x0 + 1).min(img.size.w - 1), (y0 + 1).min(img.size.h - 1));
+    let (tx, ty) = (fx - x0 as f32, fy - y0 as f32);
+
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let top = lerp(img.get(x0, y0).to_f32(), img.get(x1, y0).to_f32(), tx);
+    let bottom = lerp(img.get(x0, y1).to_f32(), img.get(x1, y1).to_f32(), tx);
+    T::from_f32(lerp(top, bottom, ty))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Resample, fit, resize, scale_by};
+    use crate::{image::Image, types::Size};
+
+    #[test]
+    fn sizes() {
+        assert_eq!(scale_by(Size::new(480, 360), 0.5), Size::new(240, 180));
+        assert_eq!(scale_by(Size::new(3, 3), 1.5), Size::new(5, 5));
+        assert_eq!(
+            fit(Size::new(480, 360), Size::new(200, 200)),
+            Size::new(200, 150)
+        );
+        assert_eq!(
+            fit(Size::new(48, 36), Size::new(400, 150)),
+            Size::new(200, 150)
+        );
+    }
+
+    #[test]
+    fn box_majority() {
+        let img = Image::new(
+            Size::new(4, 2),
+            vec![true, true, true, false, false, true, false, false],
+        );
+        let out = resize(&img, Size::new(2, 1), Resample::Box);
+        assert_eq!(out.pixels, vec![true, false]);
+
+        let img = Image::new(Size::new(3, 1), vec![0u8, 90, 180]);
+        let out = resize(&img, Size::new(2, 1), Resample::Box);
+        assert_eq!(out.pixels, vec![30, 150]);
+    }
+
+    #[test]
+    fn upscale() {
+        let img = Image::new(Size::new(2, 1), vec![0u8, 200]);
+
+        let out = resize(&img, Size::new(4, 1), Resample::Nearest);
+        assert_eq!(out.pixels, vec![0, 0, 200, 200]);
+
+        let out = resize(&img, Size::new(4, 1), Resample::Bilinear);
+        assert_eq!(out.pixels, vec![0, 50, 150, 200]);
+
+        let out = resize(&img, Size::new(4, 2), Resample::Box);
+        assert_eq!(out.pixels, vec![0, 0, 200, 200, 0, 0, 200, 200]);
+    }
+}
@@ -1,129 +1,405 @@
 use std::{
-    io::{self, Write},
+    io::{self, Stdout, Write},
     iter,
 };
 
 use crate::{
+    error::Error,
+    output::FrameWriter,
+    text::{self, TextBlock},
     traits::RenderTarget,
-    types::{Quad, Size},
+    types::{Color, Quad, Size, VQuad},
 };
 
-pub struct TermScreen {
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum BorderStyle {
+    None,
+    #[default]
+    Hash,
+    Ascii,
+    Single,
+    Double,
+    Rounded,
+    Heavy,
+}
+
+impl BorderStyle {
+    /// glyphs as [top left, top right, bottom left, bottom right, horizontal, vertical]
+    fn glyphs(&self) -> [char; 6] {
+        match self {
+            Self::None => [' '; 6],
+            Self::Hash => ['#'; 6],
+            Self::Ascii => ['+', '+', '+', '+', '-', '|'],
+            Self::Single => ['┌', '┐', '└', '┘', '─', '│'],
+            Self::Double => ['╔', '╗', '╚', '╝', '═', '║'],
+            Self::Rounded => ['╭', '╮', '╰', '╯', '─', '│'],
+            Self::Heavy => ['┏', '┓', '┗', '┛', '━', '┃'],
+        }
+    }
+
+    fn width(&self) -> usize {
+        match self {
+            Self::None => 0,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Border {
+    pub style: BorderStyle,
+    pub title: Option<String>,
+    pub title_quad: Quad,
+    pub color: Option<Color>,
+}
+
+impl Border {
+    pub fn new(style: BorderStyle) -> Self {
+        Self {
+            style,
+            ..Default::default()
+        }
+    }
+
+    pub fn titled(mut self, title: &str, quad: Quad) -> Self {
+        self.title = Some(title.to_string());
+        self.title_quad = quad;
+        self
+    }
+
+    pub fn colored(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    fn paint(&self, s: &str) -> String {
+        match self.color {
+            Some(color) => format!("{}{}{}", color.fg(), s, Color::RESET),
+            None => s.to_string(),
+        }
+    }
+
+    /// top edge of the given total width with the title embedded into it
+    fn top_line(&self, w: usize) -> String {
+        let [tl, tr, _, _, hor, _] = self.style.glyphs();
+        let inner_w = w.saturating_sub(2);
+
+        let title = match &self.title {
+            Some(t) if inner_w > 0 => text::truncate(&format!(" {} ", t), inner_w),
+            _ => String::new(),
+        };
+        let free = inner_w - text::width(&title);
+        let left = match self.title_quad {
+            Quad::Left => free.min(1),
+            Quad::Center => free / 2,
+            Quad::Right => free - free.min(1),
+        };
+
+        let left: String = iter::repeat_n(hor, left).collect();
+        let right: String = iter::repeat_n(hor, free - left.chars().count()).collect();
+        format!(
+            "{}{}{}",
+            self.paint(&format!("{}{}", tl, left)),
+            title,
+            self.paint(&format!("{}{}", right, tr))
+        )
+    }
+
+    fn bottom_line(&self, w: usize) -> String {
+        let [_, _, bl, br, hor, _] = self.style.glyphs();
+        let inner: String = iter::repeat_n(hor, w.saturating_sub(2)).collect();
+        self.paint(&format!("{}{}{}", bl, inner, br))
+    }
+}
+
+/// how a single cell is widened to compensate for terminal glyphs being taller than wide
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Aspect {
+    /// glyph followed by a space
+    #[default]
+    Spaced,
+    /// glyph printed twice, e.g. `██`
+    Doubled,
+    /// glyph followed by a fixed second glyph, e.g. `[]`, blank cells stay blank
+    Paired(char),
+    /// glyph printed once, the board is twice as tall as wide on screen
+    Single,
+}
+
+impl Aspect {
+    /// terminal columns taken by one cell
+    pub fn cell_width(&self) -> usize {
+        match self {
+            Self::Single => 1,
+            _ => 2,
+        }
+    }
+
+    fn write_cell(&self, out: &mut String, v: char) {
+        out.push(v);
+        match self {
+            Self::Spaced => out.push(' '),
+            Self::Doubled => out.push(v),
+            Self::Paired(_) if v == ' ' => out.push(' '),
+            Self::Paired(second) => out.push(*second),
+            Self::Single => {}
+        }
+    }
+}
+
+pub struct TermScreen<W: Write = Stdout> {
     full_size: Size,
     content_size: Size,
+    border: Border,
+    aspect: Aspect,
+    out: FrameWriter<W>,
 }
 
 impl TermScreen {
-    const BORDER: char = '#';
-    const BORDER_WIDTH: usize = 1;
+    pub fn new(w: usize, h: usize) -> Result<Self, Error> {
+        Self::from_size(Size::new(w, h))
+    }
 
-    pub fn new(w: usize, h: usize) -> Self {
-        Self {
-            full_size: Size::new((w + 1) * 2, h + 3),
-            content_size: Size::new(w, h),
-        }
+    pub fn from_size(s: Size) -> Result<Self, Error> {
+        Self::with_output(s, io::stdout())
     }
+}
 
-    pub fn from_size(mut s: Size) -> Self {
-        let cs = s;
-        s.w = (s.w + 1) * 2;
-        s.h += 3;
-        Self {
-            full_size: s,
-            content_size: cs,
+impl<W: Write> TermScreen<W> {
+    /// rows above the screen reserved for the status line
+    const STATUS_HEIGHT: usize = 1;
+
+    pub fn with_output(s: Size, out: W) -> Result<Self, Error> {
+        if s.flatten() == 0 {
+            return Err(Error::InvalidSize(s));
         }
+
+        let border = Border::default();
+        let aspect = Aspect::default();
+        Ok(Self {
+            full_size: Self::full_size(s, &border, aspect),
+            content_size: s,
+            border,
+            aspect,
+            out: FrameWriter::new(out),
+        })
+    }
+
+    /// wrap every frame in a synchronized update, see `FrameWriter`
+    pub fn with_sync_output(mut self, sync: bool) -> Self {
+        self.out.set_sync(sync);
+        self
+    }
+
+    pub fn with_border(mut self, border: Border) -> Self {
+        self.full_size = Self::full_size(self.content_size, &border, self.aspect);
+        self.border = border;
+        self
+    }
+
+    pub fn with_aspect(mut self, aspect: Aspect) -> Self {
+        self.full_size = Self::full_size(self.content_size, &self.border, aspect);
+        self.aspect = aspect;
+        self
+    }
+
+    fn full_size(content: Size, border: &Border, aspect: Aspect) -> Size {
+        let bw = border.style.width();
+        Size::new(
+            content.w * aspect.cell_width() + 2 * bw,
+            content.h + 2 * bw + Self::STATUS_HEIGHT,
+        )
     }
 
     pub fn get_size(&self) -> &Size {
         &self.content_size
     }
 
+    pub fn get_full_size(&self) -> &Size {
+        &self.full_size
+    }
+
+    /// 0-based terminal (row, column) of the first content cell
+    fn origin(&self) -> (usize, usize) {
+        let bw = self.border.style.width();
+        (Self::STATUS_HEIGHT + bw, bw)
+    }
+
     pub fn render_text(
-        &self,
+        &mut self,
         x: usize,
         y: usize,
         text: String,
         quad: Quad,
-    ) -> Result<(), io::Error> {
-        let offset = match quad {
+    ) -> Result<(), Error> {
+        self.render_block(x, y, &TextBlock::new(&text, quad, VQuad::Top))
+    }
+
+    /// draws the block so that its `quad`/`vquad` side is anchored at cell (x, y)
+    pub fn render_block(&mut self, x: usize, y: usize, block: &TextBlock) -> Result<(), Error> {
+        let size = block.size();
+        let h_offset = match block.quad {
             Quad::Left => 0,
-            Quad::Center => text.len() / 2,
-            Quad::Right => text.len(),
+            Quad::Center => size.w / 2,
+            Quad::Right => size.w,
+        };
+        let v_offset = match block.vquad {
+            VQuad::Top => 0,
+            VQuad::Middle => size.h / 2,
+            VQuad::Bottom => size.h.saturating_sub(1),
         };
-        let r_x = (x * 2).saturating_sub(offset).min(self.full_size.w);
-        let r_y = (y + 1).min(self.full_size.h);
-        let len = (self.full_size.w - r_x).min(text.len());
 
-        print!("\x1B[H");
-        print!("{}", "\n".repeat(r_y));
-        print!("\x1B[{}C", r_x);
-        print!(" {}", text.chars().take(len).collect::<String>());
-        io::stdout().flush()
+        let (row, col) = self.origin();
+        let r_x = (col + x * self.aspect.cell_width())
+            .saturating_sub(h_offset)
+            .min(self.full_size.w);
+        let r_y = (row + y).saturating_sub(v_offset);
+        let max_w = self.full_size.w - r_x;
+
+        for (i, line) in block.rows().iter().enumerate() {
+            if r_y + i >= self.full_size.h {
+                break;
+            }
+            write!(
+                self.out,
+                "\x1B[{};{}H{}",
+                r_y + i + 1,
+                r_x + 1,
+                text::truncate(line, max_w)
+            )?;
+        }
+        Ok(self.out.finish()?)
     }
 }
 
-impl RenderTarget<char> for TermScreen {
-    type Error = io::Error;
+impl<W: Write> RenderTarget<char> for TermScreen<W> {
+    type Error = Error;
+
+    fn init(&mut self) -> Result<(), Self::Error> {
+        write!(self.out, "\x1B[?1049h")?;
+        write!(self.out, "\x1B[?25l")?;
+        write!(self.out, "\x1B[2J\x1B[H")?;
 
-    fn init(&self) -> Result<(), Self::Error> {
-        print!("\x1B[?1049h");
-        print!("\x1B[?25l");
-        print!("\x1B[2J\x1B[H");
+        if self.border.style.width() == 0 {
+            return Ok(self.out.finish()?);
+        }
 
-        let line: String = iter::repeat_n(Self::BORDER, self.full_size.w).collect();
-        let side = format!("\n\r#\x1B[{}C#", self.full_size.w - 2 * Self::BORDER_WIDTH);
+        let [.., ver] = self.border.style.glyphs();
+        let side = self.border.paint(&ver.to_string());
+        let (row, _) = self.origin();
 
-        print!("\n\r{}", line);
-        print!("{}", side.repeat(self.content_size.h));
-        print!("\n\r{}", line);
-        io::stdout().flush()
+        let top = self.border.top_line(self.full_size.w);
+        write!(self.out, "\x1B[{};1H{}", row, top)?;
+        for i in 0..self.content_size.h {
+            write!(self.out, "\x1B[{};1H{}", row + i + 1, side)?;
+            write!(
+                self.out,
+                "\x1B[{};{}H{}",
+                row + i + 1,
+                self.full_size.w,
+                side
+            )?;
+        }
+        let bottom = self.border.bottom_line(self.full_size.w);
+        write!(
+            self.out,
+            "\x1B[{};1H{}",
+            row + self.content_size.h + 1,
+            bottom
+        )?;
+        Ok(self.out.finish()?)
     }
 
-    fn exit(&self) -> Result<(), Self::Error> {
-        print!("\x1B[?1049l");
-        print!("\x1b[?25h");
-        io::stdout().flush()
+    fn exit(&mut self) -> Result<(), Self::Error> {
+        write!(self.out, "\x1B[?1049l")?;
+        write!(self.out, "\x1b[?25h")?;
+        Ok(self.out.finish()?)
+    }
+
+    fn check_size(&self, size: Size) -> Result<(), Error> {
+        if size != self.content_size {
+            return Err(Error::SizeMismatch {
+                expected: self.content_size,
+                found: size,
+            });
+        }
+        Ok(())
     }
 
     fn draw<I>(&mut self, items: I) -> Result<(), Self::Error>
     where
         I: Iterator<Item = char>,
     {
-        println!("\x1B[H");
+        let (row, col) = self.origin();
+        let mut line = String::with_capacity(self.full_size.w);
+        let mut count = 0;
         for (i, v) in items.enumerate() {
+            count += 1;
             if i % self.content_size.w == 0 {
-                print!("\n\r\x1B[{}C", Self::BORDER_WIDTH);
+                self.out.write_all(line.as_bytes())?;
+                line.clear();
+                write!(
+                    self.out,
+                    "\x1B[{};{}H",
+                    row + i / self.content_size.w + 1,
+                    col + 1
+                )?;
             }
-            print!("{} ", v);
+            self.aspect.write_cell(&mut line, v);
         }
-        io::stdout().flush()
+        self.out.write_all(line.as_bytes())?;
+
+        if count != self.content_size.flatten() {
+            self.out.discard();
+            return Err(Error::SizeMismatch {
+                expected: self.content_size,
+                found: Size::from_flat(count, self.content_size.w),
+            });
+        }
+        Ok(self.out.finish()?)
     }
 }
 
-impl Drop for TermScreen {
+impl<W: Write> Drop for TermScreen<W> {
     fn drop(&mut self) {
-        self.exit().unwrap();
+        let _ = self.exit();
     }
 }
 
-pub struct TermStatusLine {
+pub struct TermStatusLine<W: Write = Stdout> {
     w: usize,
+    out: FrameWriter<W>,
 }
 
 impl TermStatusLine {
     pub fn new(w: usize) -> Self {
-        Self { w: (w + 1) * 2 }
+        Self::with_output((w + 1) * 2, io::stdout())
+    }
+
+    /// status line spanning the full width of the screen
+    pub fn for_screen<W: Write>(screen: &TermScreen<W>) -> Self {
+        Self::with_output(screen.full_size.w, io::stdout())
+    }
+}
+
+impl<W: Write> TermStatusLine<W> {
+    /// status line `w` terminal columns wide
+    pub fn with_output(w: usize, out: W) -> Self {
+        Self {
+            w,
+            out: FrameWriter::new(out),
+        }
     }
 }
 
-impl RenderTarget<char> for TermStatusLine {
-    type Error = io::Error;
+impl<W: Write> RenderTarget<char> for TermStatusLine<W> {
+    type Error = Error;
 
-    fn init(&self) -> Result<(), Self::Error> {
+    fn init(&mut self) -> Result<(), Self::Error> {
         Ok(())
     }
 
-    fn exit(&self) -> Result<(), Self::Error> {
+    fn exit(&mut self) -> Result<(), Self::Error> {
         Ok(())
     }
 
@@ -131,8 +407,99 @@ impl RenderTarget<char> for TermStatusLine {
     where
         I: Iterator<Item = char>,
     {
-        print!("\x1B[H");
-        print!("{}", items.take(self.w).collect::<String>());
-        io::stdout().flush()
+        let status = text::truncate(&items.collect::<String>(), self.w);
+        // a shorter status leaves nothing of the previous one behind
+        write!(self.out, "\x1B[H{}\x1B[K", status)?;
+        Ok(self.out.finish()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Aspect, Border, BorderStyle, TermScreen};
+    use crate::{
+        error::Error,
+        output::tests::CountingWriter,
+        traits::{RenderTarget, Renderble, Shaped},
+        types::{Quad, Size},
+    };
+
+    #[test]
+    fn size_errors() {
+        let res = TermScreen::with_output(Size::new(0, 20), CountingWriter::default());
+        assert!(matches!(res, Err(Error::InvalidSize(_))));
+
+        let mut screen =
+            TermScreen::with_output(Size::new(4, 4), CountingWriter::default()).unwrap();
+        let res = screen.draw("o".chars().cycle().take(10));
+        assert!(matches!(
+            res,
+            Err(Error::SizeMismatch { found, .. }) if found.w == 2 && found.h == 3
+        ));
+        assert_eq!(screen.out.get_ref().writes, 0);
+    }
+
+    #[test]
+    fn draw_sized_checks_shape() {
+        struct Line(&'static str);
+        impl Renderble for Line {
+            type Primitive = char;
+            fn render(&self) -> impl Iterator<Item = char> {
+                self.0.chars()
+            }
+        }
+
+        let mut screen =
+            TermScreen::with_output(Size::new(2, 2), CountingWriter::default()).unwrap();
+
+        let res = screen.draw_sized(&Shaped::new(&Line("abcd"), Size::new(4, 1)));
+        assert!(matches!(res, Err(Error::SizeMismatch { .. })));
+        assert_eq!(screen.out.get_ref().writes, 0);
+
+        screen
+            .draw_sized(&Shaped::new(&Line("abcd"), Size::new(2, 2)))
+            .unwrap();
+        assert_eq!(screen.out.get_ref().data, b"\x1B[3;2Ha b \x1B[4;2Hc d ");
+    }
+
+    #[test]
+    fn draw_is_one_write() {
+        let mut screen = TermScreen::with_output(Size::new(20, 20), CountingWriter::default())
+            .unwrap()
+            .with_sync_output(true);
+
+        screen.draw("o ".chars().cycle().take(400)).unwrap();
+
+        let counter = screen.out.get_ref();
+        assert_eq!(counter.writes, 1);
+        assert!(counter.data.starts_with(b"\x1B[?2026h\x1B[3;2Ho   o "));
+        assert!(counter.data.ends_with(b"\x1B[?2026l"));
+    }
+
+    #[test]
+    fn aspect_cells() {
+        let render = |aspect: Aspect| {
+            let mut out = String::new();
+            "█ o".chars().for_each(|c| aspect.write_cell(&mut out, c));
+            out
+        };
+
+        assert_eq!(render(Aspect::Spaced), "█   o ");
+        assert_eq!(render(Aspect::Doubled), "██  oo");
+        assert_eq!(render(Aspect::Paired(']')), "█]  o]");
+        assert_eq!(render(Aspect::Single), "█ o");
+    }
+
+    #[test]
+    fn border_title_placement() {
+        let border = Border::new(BorderStyle::Single).titled("hi", Quad::Center);
+        assert_eq!(border.top_line(10), "┌── hi ──┐");
+
+        let border = Border::new(BorderStyle::Ascii).titled("hi", Quad::Left);
+        assert_eq!(border.top_line(10), "+- hi ---+");
+
+        let border = Border::new(BorderStyle::Heavy).titled("a long title", Quad::Right);
+        assert_eq!(border.top_line(8), "┏ a lo…┓");
+        assert_eq!(border.bottom_line(8), "┗━━━━━━┛");
     }
 }
@@ -0,0 +1,445 @@
+use crate::types::{Quad, Size, VQuad};
+
+const ELLIPSIS: char = '…';
+
+/// ranges of zero width code points (combining marks, joiners, variation selectors)
+const ZERO_WIDTH: &[(u32, u32)] = &[
+    (0x0300, 0x036F),
+    (0x0483, 0x0489),
+    (0x0591, 0x05BD),
+    (0x05BF, 0x05BF),
+    (0x05C1, 0x05C2),
+    (0x05C4, 0x05C5),
+    (0x05C7, 0x05C7),
+    (0x0610, 0x061A),
+    (0x064B, 0x065F),
+    (0x0670, 0x0670),
+    (0x06D6, 0x06DC),
+    (0x06DF, 0x06E4),
+    (0x06E7, 0x06E8),
+    (0x06EA, 0x06ED),
+    (0x0900, 0x0902),
+    (0x093A, 0x093A),
+    (0x093C, 0x093C),
+    (0x0941, 0x0948),
+    (0x094D, 0x094D),
+    (0x0951, 0x0957),
+    (0x0E31, 0x0E31),
+    (0x0E34, 0x0E3A),
+    (0x0E47, 0x0E4E),
+    (0x1AB0, 0x1AFF),
+    (0x1DC0, 0x1DFF),
+    (0x200B, 0x200F),
+    (0x2028, 0x202E),
+    (0x2060, 0x2064),
+    (0x20D0, 0x20FF),
+    (0x302A, 0x302D),
+    (0x3099, 0x309A),
+    (0xFE00, 0xFE0F),
+    (0xFE20, 0xFE2F),
+    (0xFEFF, 0xFEFF),
+    (0x1F3FB, 0x1F3FF),
+    (0xE0000, 0xE007F),
+    (0xE0100, 0xE01EF),
+];
+
+/// ranges of East Asian wide / fullwidth code points and emoji with wide presentation
+const WIDE: &[(u32, u32)] = &[
+    (0x1100, 0x115F),
+    (0x231A, 0x231B),
+    (0x2329, 0x232A),
+    (0x23E9, 0x23EC),
+    (0x23F0, 0x23F0),
+    (0x23F3, 0x23F3),
+    (0x25FD, 0x25FE),
+    (0x2614, 0x2615),
+    (0x2648, 0x2653),
+    (0x267F, 0x267F),
+    (0x2693, 0x2693),
+    (0x26A1, 0x26A1),
+    (0x26AA, 0x26AB),
+    (0x26BD, 0x26BE),
+    (0x26C4, 0x26C5),
+    (0x26CE, 0x26CE),
+    (0x26D4, 0x26D4),
+    (0x26EA, 0x26EA),
+    (0x26F2, 0x26F3),
+    (0x26F5, 0x26F5),
+    (0x26FA, 0x26FA),
+    (0x26FD, 0x26FD),
+    (0x2705, 0x2705),
+    (0x270A, 0x270B),
+    (0x2728, 0x2728),
+    (0x274C, 0x274C),
+    (0x274E, 0x274E),
+    (0x2753, 0x2755),
+    (0x2757, 0x2757),
+    (0x2795, 0x2797),
+    (0x27B0, 0x27B0),
+    (0x27BF, 0x27BF),
+    (0x2B1B, 0x2B1C),
+    (0x2B50, 0x2B50),
+    (0x2B55, 0x2B55),
+    (0x2E80, 0x303E),
+    (0x3041, 0x33FF),
+    (0x3400, 0x4DBF),
+    (0x4E00, 0x9FFF),
+    (0xA000, 0xA4CF),
+    (0xA960, 0xA97F),
+    (0xAC00, 0xD7A3),
+    (0xF900, 0xFAFF),
+    (0xFE10, 0xFE19),
+    (0xFE30, 0xFE6F),
+    (0xFF00, 0xFF60),
+    (0xFFE0, 0xFFE6),
+    (0x16FE0, 0x16FE4),
+    (0x17000, 0x18CFF),
+    (0x1B000, 0x1B2FF),
+    (0x1F004, 0x1F004),
+    (0x1F0CF, 0x1F0CF),
+    (0x1F18E, 0x1F18E),
+    (0x1F191, 0x1F19A),
+    (0x1F200, 0x1F202),
+    (0x1F210, 0x1F23B),
+    (0x1F240, 0x1F248),
+    (0x1F250, 0x1F251),
+    (0x1F260, 0x1F265),
+    (0x1F300, 0x1F320),
+    (0x1F32D, 0x1F335),
+    (0x1F337, 0x1F37C),
+    (0x1F37E, 0x1F393),
+    (0x1F3A0, 0x1F3CA),
+    (0x1F3CF, 0x1F3D3),
+    (0x1F3E0, 0x1F3F0),
+    (0x1F3F4, 0x1F3F4),
+    (0x1F3F8, 0x1F43E),
+    (0x1F440, 0x1F440),
+    (0x1F442, 0x1F4FC),
+    (0x1F4FF, 0x1F53D),
+    (0x1F54B, 0x1F54E),
+    (0x1F550, 0x1F567),
+    (0x1F57A, 0x1F57A),
+    (0x1F595, 0x1F596),
+    (0x1F5A4, 0x1F5A4),
+    (0x1F5FB, 0x1F64F),
+    (0x1F680, 0x1F6C5),
+    (0x1F6CC, 0x1F6CC),
+    (0x1F6D0, 0x1F6D2),
+    (0x1F6D5, 0x1F6D7),
+    (0x1F6DC, 0x1F6DF),
+    (0x1F6EB, 0x1F6EC),
+    (0x1F6F4, 0x1F6FC),
+    (0x1F7E0, 0x1F7EB),
+    (0x1F7F0, 0x1F7F0),
+    (0x1F90C, 0x1F93A),
+    (0x1F93C, 0x1F945),
+    (0x1F947, 0x1F9FF),
+    (0x1FA70, 0x1FAFF),
+    (0x20000, 0x2FFFD),
+    (0x30000, 0x3FFFD),
+];
+
+const ZWJ: char = '\u{200D}';
+const VS16: char = '\u{FE0F}';
+
+fn in_table(table: &[(u32, u32)], c: char) -> bool {
+    let c = c as u32;
+    table
+        .binary_search_by(|&(lo, hi)| {
+            if hi < c {
+                std::cmp::Ordering::Less
+            } else if lo > c {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+/// number of terminal columns taken by a single code point, control characters take none
+pub fn char_width(c: char) -> usize {
+    if c.is_control() || in_table(ZERO_WIDTH, c) {
+        0
+    } else if in_table(WIDE, c) {
+        2
+    } else {
+        1
+    }
+}
+
+/// iterator over (simplified) extended grapheme clusters of a string
+pub struct Graphemes<'a> {
+    rest: &'a str,
+}
+
+pub fn graphemes(s: &str) -> Graphemes<'_> {
+    Graphemes { rest: s }
+}
+
+impl<'a> Iterator for Graphemes<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut chars = self.rest.char_indices();
+        let (_, first) = chars.next()?;
+
+        let mut end = first.len_utf8();
+        let mut prev = first;
+        for (i, c) in chars {
+            let joins = prev == ZWJ
+                || (char_width(c) == 0 && !c.is_control())
+                || (i == 1 && first == '\r' && c == '\n')
+                || (i == 4 && is_regional_indicator(first) && is_regional_indicator(c));
+
+            if !joins {
+                break;
+            }
+
+            end = i + c.len_utf8();
+            prev = c;
+        }
+
+        let (cluster, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Some(cluster)
+    }
+}
+
+/// number of terminal columns taken by a single grapheme cluster
+pub fn grapheme_width(g: &str) -> usize {
+    let mut chars = g.chars();
+    let Some(first) = chars.next() else {
+        return 0;
+    };
+
+    if is_regional_indicator(first) || g.contains(VS16) || g.contains(ZWJ) {
+        return 2;
+    }
+    char_width(first)
+}
+
+/// number of terminal columns taken by a string
+pub fn width(s: &str) -> usize {
+    graphemes(s).map(grapheme_width).sum()
+}
+
+/// cuts the string to at most `max` columns without splitting clusters, no ellipsis
+pub fn clip(s: &str, max: usize) -> &str {
+    let mut used = 0;
+    let mut end = 0;
+    for g in graphemes(s) {
+        used += grapheme_width(g);
+        if used > max {
+            break;
+        }
+        end += g.len();
+    }
+    &s[..end]
+}
+
+/// cuts the string to at most `max` columns, marking the cut with an ellipsis
+pub fn truncate(s: &str, max: usize) -> String {
+    if width(s) <= max {
+        return s.to_string();
+    }
+    if max == 0 {
+        return String::new();
+    }
+
+    let mut out = clip(s, max - 1).to_string();
+    out.push(ELLIPSIS);
+    out
+}
+
+/// truncates the string and fills it with spaces up to exactly `w` columns
+pub fn pad(s: &str, w: usize, quad: Quad) -> String {
+    let s = truncate(s, w);
+    let free = w - width(&s);
+
+    let left = match quad {
+        Quad::Left => 0,
+        Quad::Center => free / 2,
+        Quad::Right => free,
+    };
+
+    format!("{}{}{}", " ".repeat(left), s, " ".repeat(free - left))
+}
+
+/// greedily wraps every line of the text into lines of at most `max` columns,
+/// words wider than `max` are split between clusters
+pub fn wrap(s: &str, max: usize) -> Vec<String> {
+    let max = max.max(1);
+    let mut out = Vec::new();
+
+    for line in s.lines() {
+        let mut cur = String::new();
+        let mut cur_w = 0;
+
+        for mut word in line.split_whitespace() {
+            let mut word_w = width(word);
+
+            if cur_w > 0 && cur_w + 1 + word_w <= max {
+                cur.push(' ');
+                cur.push_str(word);
+                cur_w += 1 + word_w;
+                continue;
+            }
+
+            if cur_w > 0 {
+                out.push(std::mem::take(&mut cur));
+            }
+
+            while word_w > max {
+                let head = clip(word, max);
+                let head = if head.is_empty() {
+                    graphemes(word).next().unwrap_or(word)
+                } else {
+                    head
+                };
+                out.push(head.to_string());
+                word = &word[head.len()..];
+                word_w = width(word);
+            }
+
+            cur.push_str(word);
+            cur_w = word_w;
+        }
+
+        out.push(cur);
+    }
+
+    out
+}
+
+/// multi-line text anchored to a point, see `TermScreen::render_block`
+pub struct TextBlock {
+    pub lines: Vec<String>,
+    pub quad: Quad,
+    pub vquad: VQuad,
+    /// pad the lines into a box with a one cell margin, erasing whatever is underneath
+    pub clear: bool,
+}
+
+impl TextBlock {
+    pub fn new(text: &str, quad: Quad, vquad: VQuad) -> Self {
+        Self {
+            lines: text.lines().map(str::to_string).collect(),
+            quad,
+            vquad,
+            clear: false,
+        }
+    }
+
+    pub fn wrap(mut self, max: usize) -> Self {
+        self.lines = wrap(&self.lines.join("\n"), max);
+        self
+    }
+
+    pub fn cleared(mut self) -> Self {
+        self.clear = true;
+        self
+    }
+
+    /// size of the block in columns and rows, including the margin of a cleared box
+    pub fn size(&self) -> Size {
+        let margin = 2 * self.clear as usize;
+        let w = self.lines.iter().map(|l| width(l)).max().unwrap_or(0);
+        Size::new(w + margin, self.lines.len() + margin)
+    }
+
+    /// lines padded and aligned to the block width
+    pub fn rows(&self) -> Vec<String> {
+        let size = self.size();
+
+        if !self.clear {
+            let w = size.w;
+            return self.lines.iter().map(|l| pad(l, w, self.quad)).collect();
+        }
+
+        let blank = " ".repeat(size.w);
+        let inner = size.w - 2;
+
+        let mut rows = Vec::with_capacity(size.h);
+        rows.push(blank.clone());
+        rows.extend(
+            self.lines
+                .iter()
+                .map(|l| format!(" {} ", pad(l, inner, self.quad))),
+        );
+        rows.push(blank);
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TextBlock, char_width, graphemes, pad, truncate, width, wrap};
+    use crate::types::{Quad, VQuad};
+
+    #[test]
+    fn width_ascii_and_wide() {
+        assert_eq!(width("snake"), 5);
+        assert_eq!(width("日本語"), 6);
+        assert_eq!(width("ｓｎａｋｅ"), 10);
+        assert_eq!(char_width('█'), 1);
+        assert_eq!(char_width('⣿'), 1);
+        assert_eq!(char_width('🬞'), 1);
+    }
+
+    #[test]
+    fn width_combining_and_emoji() {
+        assert_eq!(width("e\u{301}"), 1);
+        assert_eq!(graphemes("e\u{301}x").count(), 2);
+        assert_eq!(width("🐍"), 2);
+        assert_eq!(width("👩‍💻"), 2);
+        assert_eq!(graphemes("👩‍💻!").count(), 2);
+        assert_eq!(width("🇸🇰"), 2);
+        assert_eq!(graphemes("🇸🇰🇨🇿").count(), 2);
+        assert_eq!(width("❤\u{FE0F}"), 2);
+    }
+
+    #[test]
+    fn truncate_keeps_clusters() {
+        assert_eq!(truncate("snake", 5), "snake");
+        assert_eq!(truncate("snake!", 5), "snak…");
+        assert_eq!(truncate("日本語", 4), "日…");
+        assert_eq!(truncate("日本語", 3), "日…");
+        assert_eq!(truncate("e\u{301}e\u{301}e\u{301}", 2), "e\u{301}…");
+        assert_eq!(truncate("abc", 0), "");
+    }
+
+    #[test]
+    fn pad_aligns() {
+        assert_eq!(pad("ab", 6, Quad::Left), "ab    ");
+        assert_eq!(pad("ab", 6, Quad::Center), "  ab  ");
+        assert_eq!(pad("ab", 6, Quad::Right), "    ab");
+        assert_eq!(pad("日本", 5, Quad::Center), "日本 ");
+        assert_eq!(pad("日本語", 5, Quad::Left), "日本…");
+    }
+
+    #[test]
+    fn wrap_words() {
+        assert_eq!(wrap("to start press e", 8), vec!["to start", "press e"]);
+        assert_eq!(wrap("a\n\nb", 8), vec!["a", "", "b"]);
+        assert_eq!(wrap("abcdefgh ij", 3), vec!["abc", "def", "gh", "ij"]);
+        assert_eq!(wrap("日本語", 3), vec!["日", "本", "語"]);
+    }
+
+    #[test]
+    fn block_rows() {
+        let block = TextBlock::new("You died!\nq", Quad::Center, VQuad::Middle);
+        assert_eq!(block.rows(), vec!["You died!", "    q    "]);
+
+        let block = block.cleared();
+        assert_eq!(block.size().w, 11);
+        assert_eq!(
+            block.rows(),
+            vec!["           ", " You died! ", "     q     ", "           "]
+        );
+    }
+}
@@ -1,14 +1,67 @@
+use crate::{error::Error, types::Size};
+
 pub trait Renderble {
     type Primitive;
     fn render(&self) -> impl Iterator<Item = Self::Primitive>;
 }
 
+/// 2D content that knows its own dimensions, every row yields exactly `size().w` items
+pub trait SizedRenderble {
+    type Primitive;
+    fn size(&self) -> Size;
+    fn render_rows(&self) -> impl Iterator<Item = impl Iterator<Item = Self::Primitive>>;
+}
+
 pub trait RenderTarget<P> {
     type Error;
 
-    fn init(&self) -> Result<(), Self::Error>;
-    fn exit(&self) -> Result<(), Self::Error>;
+    fn init(&mut self) -> Result<(), Self::Error>;
+    fn exit(&mut self) -> Result<(), Self::Error>;
     fn draw<I>(&mut self, items: I) -> Result<(), Self::Error>
     where
         I: Iterator<Item = P>;
+
+    /// fails if content of the given size cannot be drawn on this target
+    fn check_size(&self, _size: Size) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// draws 2D content after validating its size against the target
+    fn draw_sized<S>(&mut self, content: &S) -> Result<(), Self::Error>
+    where
+        S: SizedRenderble<Primitive = P>,
+        Self::Error: From<Error>,
+    {
+        self.check_size(content.size())?;
+        self.draw(content.render_rows().flatten())
+    }
+}
+
+/// adapter giving a flat `Renderble` a shape, rows are cut from its output every `size.w` items
+pub struct Shaped<'a, R> {
+    inner: &'a R,
+    size: Size,
+}
+
+impl<'a, R> Shaped<'a, R> {
+    pub fn new(inner: &'a R, size: Size) -> Self {
+        Self { inner, size }
+    }
+}
+
+impl<R> SizedRenderble for Shaped<'_, R>
+where
+    R: Renderble,
+{
+    type Primitive = R::Primitive;
+
+    fn size(&self) -> Size {
+        self.size
+    }
+
+    fn render_rows(&self) -> impl Iterator<Item = impl Iterator<Item = Self::Primitive>> {
+        let mut flat = self.inner.render();
+        let w = self.size.w;
+        (0..self.size.h).map(move |_| flat.by_ref().take(w).collect::<Vec<_>>().into_iter())
+    }
 }
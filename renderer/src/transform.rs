@@ -0,0 +1,253 @@
+use crate::{
+    image::{Image, Rgb},
+    types::{Rect, Size},
+};
+
+/// pixels the transforms can produce on their own
+pub trait Pixel: Copy {
+    /// fill used for padding
+    const BLANK: Self;
+    fn invert(self) -> Self;
+}
+
+impl Pixel for bool {
+    const BLANK: Self = false;
+    fn invert(self) -> Self {
+        !self
+    }
+}
+
+impl Pixel for u8 {
+    const BLANK: Self = 0;
+    fn invert(self) -> Self {
+        u8::MAX - self
+    }
+}
+
+impl Pixel for Rgb {
+    const BLANK: Self = [0; 3];
+    fn invert(self) -> Self {
+        self.map(|c| u8::MAX - c)
+    }
+}
+
+/// clockwise rotation
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Rotation {
+    R90,
+    R180,
+    R270,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Transform {
+    /// rectangle clamped to the image
+    Crop(Rect),
+    FlipH,
+    FlipV,
+    Rotate(Rotation),
+    Invert,
+    /// centers the image on a blank canvas, dimensions larger than the canvas are cropped
+    Pad(Size),
+}
+
+impl Transform {
+    pub fn apply<T: Pixel>(&self, img: &Image<T>) -> Image<T> {
+        match *self {
+            Self::Crop(rect) => crop(img, rect),
+            Self::FlipH => flip_h(img),
+            Self::FlipV => flip_v(img),
+            Self::Rotate(rot) => rotate(img, rot),
+            Self::Invert => img.map(|p| p.invert()),
+            Self::Pad(size) => pad(img, size),
+        }
+    }
+
+    pub fn output_size(&self, size: Size) -> Size {
+        match *self {
+            Self::Crop(rect) => clamp(rect, size).size(),
+            Self::Rotate(Rotation::R90 | Rotation::R270) => Size::new(size.h, size.w),
+            Self::Pad(to) => to,
+            _ => size,
+        }
+    }
+}
+
+fn clamp(rect: Rect, size: Size) -> Rect {
+    let x = rect.x.min(size.w);
+    let y = rect.y.min(size.h);
+    Rect::new(x, y, rect.w.min(size.w - x), rect.h.min(size.h - y))
+}
+
+pub fn crop<T: Copy>(img: &Image<T>, rect: Rect) -> Image<T> {
+    let rect = clamp(rect, img.size);
+    let pixels = img
+        .rows()
+        .skip(rect.y)
+        .take(rect.h)
+        .flat_map(|row| row[rect.x..rect.x + rect.w].iter().copied())
+        .collect();
+    Image::new(rect.size(), pixels)
+}
+
+pub fn flip_h<T: Copy>(img: &Image<T>) -> Image<T> {
+    let pixels = img
+        .rows()
+        .flat_map(|row| row.iter().rev().copied())
+        .collect();
+    Image::new(img.size, pixels)
+}
+
+pub fn flip_v<T: Copy>(img: &Image<T>) -> Image<T> {
+    let pixels = img
+        .rows()
+        .rev()
+        .flat_map(|row| row.iter().copied())
+        .collect();
+    Image::new(img.size, pixels)
+}
+
+pub fn rotate<T: Copy>(img: &Image<T>, rot: Rotation) -> Image<T> {
+    let Size { w, h } = img.size;
+    match rot {
+        Rotation::R180 => {
+            let pixels = img.pixels.iter().rev().copied().collect();
+            Image::new(img.size, pixels)
+        }
+        Rotation::R90 => {
+            let pixels = (0..w)
+                .flat_map(|x| (0..h).rev().map(move |y| (x, y)))
+                .map(|(x, y)| *img.get(x, y))
+                .collect();
+            Image::new(Size::new(h, w), pixels)
+        }
+        Rotation::R270 => {
+            let pixels = (0..w)
+                .rev()
+                .flat_map(|x| (0..h).map(move |y| (x, y)))
+                .map(|(x, y)| *img.get(x, y))
+                .collect();
+            Image::new(Size::new(h, w), pixels)
+        }
+    }
+}
+
+pub fn pad<T: Pixel>(img: &Image<T>, size: Size) -> Image<T> {
+    let src_x = img.size.w.saturating_sub(size.w) / 2;
+    let src_y = img.size.h.saturating_sub(size.h) / 2;
+    let inner = crop(img, Rect::new(src_x, src_y, size.w, size.h));
+
+    let off_x = (size.w - inner.size.w) / 2;
+    let off_y = (size.h - inner.size.h) / 2;
+
+    let mut out = Image::filled(size, T::BLANK);
+    for (y, row) in inner.rows().enumerate() {
+        let start = (off_y + y) * size.w + off_x;
+        out.pixels[start..start + row.len()].copy_from_slice(row);
+    }
+    out
+}
+
+/// transforms applied one after another
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Pipeline {
+    steps: Vec<Transform>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn then(mut self, t: Transform) -> Self {
+        self.steps.push(t);
+        self
+    }
+
+    pub fn push(&mut self, t: Transform) {
+        self.steps.push(t);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn output_size(&self, size: Size) -> Size {
+        self.steps.iter().fold(size, |s, t| t.output_size(s))
+    }
+
+    pub fn apply<T: Pixel>(&self, img: Image<T>) -> Image<T> {
+        self.steps.iter().fold(img, |img, t| t.apply(&img))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pipeline, Rotation, Transform};
+    use crate::{
+        image::Image,
+        types::{Rect, Size},
+    };
+
+    /// 3x2
+    /// 1 2 3
+    /// 4 5 6
+    fn sample() -> Image<u8> {
+        Image::new(Size::new(3, 2), vec![1, 2, 3, 4, 5, 6])
+    }
+
+    #[test]
+    fn flips_and_rotations() {
+        let img = sample();
+        assert_eq!(Transform::FlipH.apply(&img).pixels, vec![3, 2, 1, 6, 5, 4]);
+        assert_eq!(Transform::FlipV.apply(&img).pixels, vec![4, 5, 6, 1, 2, 3]);
+
+        let r90 = Transform::Rotate(Rotation::R90).apply(&img);
+        assert_eq!(r90.size, Size::new(2, 3));
+        assert_eq!(r90.pixels, vec![4, 1, 5, 2, 6, 3]);
+
+        let r270 = Transform::Rotate(Rotation::R270).apply(&img);
+        assert_eq!(r270.pixels, vec![3, 6, 2, 5, 1, 4]);
+
+        let r180 = Transform::Rotate(Rotation::R180).apply(&img);
+        assert_eq!(r180.pixels, vec![6, 5, 4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn crop_pad_invert() {
+        let img = sample();
+
+        let cropped = Transform::Crop(Rect::new(1, 0, 5, 1)).apply(&img);
+        assert_eq!(cropped.size, Size::new(2, 1));
+        assert_eq!(cropped.pixels, vec![2, 3]);
+
+        let padded = Transform::Pad(Size::new(5, 4)).apply(&img);
+        assert_eq!(
+            padded.pixels,
+            vec![0, 0, 0, 0, 0, 0, 1, 2, 3, 0, 0, 4, 5, 6, 0, 0, 0, 0, 0, 0]
+        );
+
+        let shrunk = Transform::Pad(Size::new(1, 2)).apply(&img);
+        assert_eq!(shrunk.pixels, vec![2, 5]);
+
+        assert_eq!(Transform::Invert.apply(&img).pixels[0], 254);
+    }
+
+    #[test]
+    fn pipeline() {
+        let pipeline = Pipeline::new()
+            .then(Transform::Crop(Rect::new(0, 0, 2, 2)))
+            .then(Transform::Rotate(Rotation::R90))
+            .then(Transform::Pad(Size::new(2, 3)));
+
+        assert_eq!(pipeline.output_size(Size::new(3, 2)), Size::new(2, 3));
+        assert_eq!(pipeline.apply(sample()).pixels, vec![4, 1, 5, 2, 0, 0]);
+
+        let img = Image::new(Size::new(2, 1), vec![true, false]);
+        let out = Pipeline::new()
+            .then(Transform::Invert)
+            .then(Transform::FlipH)
+            .apply(img);
+        assert_eq!(out.pixels, vec![true, false]);
+    }
+}
@@ -1,4 +1,4 @@
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub struct Size {
     pub w: usize,
     pub h: usize,
@@ -16,11 +16,123 @@ impl Size {
     pub fn flatten(&self) -> usize {
         self.h * self.w
     }
+
+    /// shape of `len` items laid out in rows of `w`, `w` of the result is the length of the last row
+    pub fn from_flat(len: usize, w: usize) -> Size {
+        match len % w {
+            0 => Size::new(w, len / w),
+            rest => Size::new(rest, len / w + 1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub w: usize,
+    pub h: usize,
 }
 
-#[derive(Eq, PartialEq)]
+impl Rect {
+    pub fn new(x: usize, y: usize, w: usize, h: usize) -> Rect {
+        Rect { x, y, w, h }
+    }
+
+    pub fn size(&self) -> Size {
+        Size::new(self.w, self.h)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
 pub enum Quad {
+    #[default]
     Left,
     Center,
     Right,
 }
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum VQuad {
+    Top,
+    Middle,
+    Bottom,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    /// one of the 256 palette colors
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    pub const RESET: &str = "\x1B[0m";
+
+    /// escape sequence switching the foreground to this color
+    pub fn fg(&self) -> String {
+        match self {
+            Self::Indexed(i) => format!("\x1B[38;5;{}m", i),
+            Self::Rgb(r, g, b) => format!("\x1B[38;2;{};{};{}m", r, g, b),
+            basic => format!("\x1B[{}m", 30 + basic.basic_index()),
+        }
+    }
+
+    /// components as drawn by the default xterm palette
+    pub fn to_rgb(&self) -> (u8, u8, u8) {
+        const BASIC: [(u8, u8, u8); 16] = [
+            (0, 0, 0),
+            (205, 0, 0),
+            (0, 205, 0),
+            (205, 205, 0),
+            (0, 0, 238),
+            (205, 0, 205),
+            (0, 205, 205),
+            (229, 229, 229),
+            (127, 127, 127),
+            (255, 0, 0),
+            (0, 255, 0),
+            (255, 255, 0),
+            (92, 92, 255),
+            (255, 0, 255),
+            (0, 255, 255),
+            (255, 255, 255),
+        ];
+        const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+        match *self {
+            Self::Rgb(r, g, b) => (r, g, b),
+            Self::Indexed(i @ 0..16) => BASIC[i as usize],
+            Self::Indexed(i @ 16..232) => {
+                let i = (i - 16) as usize;
+                (CUBE[i / 36], CUBE[i / 6 % 6], CUBE[i % 6])
+            }
+            Self::Indexed(i) => {
+                let v = 8 + (i - 232) * 10;
+                (v, v, v)
+            }
+            basic => BASIC[basic.basic_index() as usize],
+        }
+    }
+
+    fn basic_index(&self) -> u8 {
+        match self {
+            Self::Black => 0,
+            Self::Red => 1,
+            Self::Green => 2,
+            Self::Yellow => 3,
+            Self::Blue => 4,
+            Self::Magenta => 5,
+            Self::Cyan => 6,
+            Self::White | Self::Indexed(_) | Self::Rgb(..) => 7,
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::{collections::VecDeque, sync::mpsc::Receiver, time::Duration};
+
+use rand::Rng;
+use renderer::{
+    game_loop::{Control, Game, LoopStats},
+    term_display::{TermScreen, TermStatusLine},
+    Error,
+};
+
+use crate::snake::{Dir, Snake};
+
+/// milliseconds per move for each difficulty, anything harder moves every `FASTEST`
+const DIFF_RATE: [u64; 5] = [150, 145, 140, 135, 130];
+const FASTEST: u64 = 60;
+/// turns kept for the coming moves, more are ignored
+const MAX_TURNS: usize = 3;
+
+/// what the input thread forwards to the game
+pub enum Input {
+    Turn(Dir),
+    Pause,
+    Quit,
+}
+
+/// one move per update, the loop step follows the difficulty
+pub struct SnakeGame<R: Rng> {
+    pub snake: Snake<R>,
+    pub screen: TermScreen,
+    pub statusline: TermStatusLine,
+    input: Receiver<Input>,
+    /// one is taken per move, so quick turns cannot reverse the snake into itself
+    turns: VecDeque<Dir>,
+    diff: usize,
+    paused: bool,
+}
+
+impl<R: Rng> SnakeGame<R> {
+    pub fn new(
+        snake: Snake<R>,
+        screen: TermScreen,
+        statusline: TermStatusLine,
+        input: Receiver<Input>,
+    ) -> Self {
+        Self {
+            snake,
+            screen,
+            statusline,
+            input,
+            turns: VecDeque::new(),
+            diff: 0,
+            paused: false,
+        }
+    }
+
+    /// time between two moves at the current difficulty
+    pub fn step(&self) -> Duration {
+        Duration::from_millis(*DIFF_RATE.get(self.diff).unwrap_or(&FASTEST))
+    }
+}
+
+impl<R: Rng> Game for SnakeGame<R> {
+    type Error = Error;
+
+    fn poll(&mut self) -> Control {
+        while let Ok(input) = self.input.try_recv() {
+            match input {
+                Input::Turn(dir) => {
+                    if self.turns.len() < MAX_TURNS {
+                        self.turns.push_back(dir);
+                    }
+                }
+                Input::Quit => return Control::Stop,
+                Input::Pause => {
+                    self.paused = !self.paused;
+                    return if self.paused {
+                        Control::Pause
+                    } else {
+                        Control::Resume
+                    };
+                }
+            }
+        }
+        Control::Continue
+    }
+
+    fn update(&mut self, _step: Duration) -> Result<Control, Error> {
+        if let Some(dir) = self.turns.pop_front() {
+            self.snake.change_dir(dir);
+        }
+        match self.snake.tick_move() {
+            Ok(diff) => {
+                self.diff = diff;
+                Ok(Control::Continue)
+            }
+            Err(()) => Ok(Control::Stop),
+        }
+    }
+
+    fn render(&mut self, _alpha: f32, _stats: &LoopStats) -> Result<(), Error> {
+        self.snake.draw_snake_to(&mut self.screen)?;
+        self.snake.draw_status_to(&mut self.statusline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, time::Duration};
+
+    use rand::{rngs::StdRng, SeedableRng};
+    use renderer::{
+        game_loop::Game,
+        term_display::{TermScreen, TermStatusLine},
+        types::Size,
+    };
+
+    use super::{Input, SnakeGame};
+    use crate::snake::{Dir, Snake, Tile};
+
+    #[test]
+    fn one_turn_per_move() {
+        let size = Size::new(10, 10);
+        let (tx, rx) = mpsc::channel();
+        let mut game = SnakeGame::new(
+            Snake::new(size, StdRng::seed_from_u64(1)),
+            TermScreen::new(size.w, size.h).unwrap(),
+            TermStatusLine::new(size.w),
+            rx,
+        );
+        let head = |game: &SnakeGame<StdRng>| {
+            game.snake
+                .tiles()
+                .iter()
+                .position(|t| *t == Tile::Snake)
+                .unwrap()
+        };
+
+        // heading up, left and then down within one tick would reverse it
+        tx.send(Input::Turn(Dir::Left)).unwrap();
+        tx.send(Input::Turn(Dir::Down)).unwrap();
+        game.poll();
+        game.update(Duration::ZERO).unwrap();
+        assert_eq!(head(&game), size.middle() - 1);
+
+        game.update(Duration::ZERO).unwrap();
+        assert_eq!(head(&game), size.middle() - 1 + size.w);
+    }
+}
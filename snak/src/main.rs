@@ -1,38 +1,55 @@
 use std::{
     env,
     io::{self, Read},
+    process,
     sync::mpsc,
     thread,
     time::Duration,
 };
 
+use game::{Input, SnakeGame};
 use rand::thread_rng;
 use raw_tty::IntoRawMode;
 use renderer::{
-    term_display::{TermScreen, TermStatusLine},
+    anim::{Animation, Easing, Tween},
+    caps::Capabilities,
+    game_loop::GameLoop,
+    term_display::{Aspect, Border, BorderStyle, TermScreen, TermStatusLine},
+    text::TextBlock,
     traits::RenderTarget,
-    types::{Quad, Size},
+    types::{Quad, Size, VQuad},
+    Error,
 };
 
+mod game;
 mod render;
 mod snake;
 
-fn play_snake(w: usize, h: usize) -> Result<(), io::Error> {
+const BANNER_DROP: Duration = Duration::from_millis(800);
+const BANNER_STEP: Duration = Duration::from_millis(30);
+
+fn play_snake(w: usize, h: usize, aspect: Aspect) -> Result<(), Error> {
     let (lock_tx, lock_rx) = mpsc::channel::<bool>();
-    let (stop_tx, stop_rx) = mpsc::channel::<bool>();
-    let (dir_tx, dir_rx) = mpsc::channel::<snake::Dir>();
+    let (input_tx, input_rx) = mpsc::channel::<Input>();
 
     let rng = thread_rng();
 
     let mut snake = snake::Snake::new(Size::new(w, h), rng);
-    let mut screen = TermScreen::new(w, h);
-    let mut statusline = TermStatusLine::new(w);
+    let caps = Capabilities::from_env();
+    let style = if caps.blocks {
+        BorderStyle::Rounded
+    } else {
+        BorderStyle::Ascii
+    };
+
+    let mut screen = TermScreen::new(w, h)?
+        .with_border(Border::new(style).titled("Snake", Quad::Center))
+        .with_aspect(aspect)
+        .with_sync_output(caps.sync_output);
+    let mut statusline = TermStatusLine::for_screen(&screen);
     screen.init()?;
     statusline.init()?;
 
-    let diff_rate = [150, 145, 140, 135, 130];
-    let mut sleep;
-
     // auxiliary thread here -> tracking user inputs
     let input = thread::spawn(move || {
         let mut raw_stdin = io::stdin().into_raw_mode().unwrap();
@@ -41,41 +58,39 @@ fn play_snake(w: usize, h: usize) -> Result<(), io::Error> {
         loop {
             raw_stdin.read_exact(&mut buf).unwrap();
 
-            let dir = match buf[0] {
+            let input = match buf[0] {
                 // kill switch
                 3 | b'q' => {
-                    stop_tx.send(true).unwrap();
+                    let _ = input_tx.send(Input::Quit);
                     break;
                 }
                 // start game (small e)
                 b'e' => {
-                    lock_tx.send(true).unwrap();
+                    let _ = lock_tx.send(true);
                     None
                 }
+                b'p' => Some(Input::Pause),
                 // arrows or capital A, B, C, D
-                b'A' => Some(snake::Dir::Up),
-                b'B' => Some(snake::Dir::Down),
-                b'C' => Some(snake::Dir::Right),
-                b'D' => Some(snake::Dir::Left),
+                b'A' => Some(Input::Turn(snake::Dir::Up)),
+                b'B' => Some(Input::Turn(snake::Dir::Down)),
+                b'C' => Some(Input::Turn(snake::Dir::Right)),
+                b'D' => Some(Input::Turn(snake::Dir::Left)),
                 _ => None,
             };
 
-            // send change in direction to main thread
-            if let Some(x) = dir {
-                dir_tx.send(x).unwrap();
+            // send input to main thread
+            if let Some(x) = input {
+                let _ = input_tx.send(x);
             }
         }
     });
 
     let mid_x = w / 2;
     let mid_y = h / 2;
-    screen.render_text(mid_x, mid_y, "Snake!".to_string(), Quad::Center)?;
-    screen.render_text(
-        mid_x,
-        mid_y + 1,
-        "To start, press e!".to_string(),
-        Quad::Center,
-    )?;
+    let title = TextBlock::new("Snake!\nTo start, press e!", Quad::Center, VQuad::Middle)
+        .wrap((w * aspect.cell_width()).saturating_sub(2))
+        .cleared();
+    screen.render_block(mid_x, mid_y, &title)?;
 
     // waiting for unlock
     if lock_rx.recv().is_ok() {
@@ -83,36 +98,34 @@ fn play_snake(w: usize, h: usize) -> Result<(), io::Error> {
     }
 
     // main thread here -> moves snake around
-    loop {
-        if stop_rx.try_recv().is_ok() {
-            break;
-        }
+    let mut game = SnakeGame::new(snake, screen, statusline, input_rx);
+    let mut game_loop = GameLoop::new(game.step());
+    while game_loop.tick(&mut game)? {
+        game_loop.set_step(game.step());
+    }
 
-        if let Ok(x) = dir_rx.try_recv() {
-            snake.change_dir(x);
-        }
+    let SnakeGame {
+        snake, mut screen, ..
+    } = game;
 
-        let r_diff = snake.tick_move();
+    let banner = TextBlock::new(
+        "You have died!\nTo exit press q!",
+        Quad::Center,
+        VQuad::Middle,
+    )
+    .wrap((w * aspect.cell_width()).saturating_sub(2))
+    .cleared();
+
+    // the banner drops in from the top border
+    let top = banner.size().h / 2;
+    let mut drop = Tween::new(top, mid_y.max(top), BANNER_DROP).with_easing(Easing::BounceOut);
+    while !drop.is_done() {
+        drop.advance(BANNER_STEP);
         snake.draw_snake_to(&mut screen)?;
-        snake.draw_status_to(&mut statusline)?;
-
-        if let Ok(diff) = r_diff {
-            sleep = *diff_rate.get(diff).unwrap_or(&60);
-        } else {
-            break;
-        }
-
-        thread::sleep(Duration::from_millis(sleep))
+        screen.render_block(mid_x, drop.value(), &banner)?;
+        thread::sleep(BANNER_STEP);
     }
 
-    screen.render_text(mid_x, mid_y, "You have died!".to_string(), Quad::Center)?;
-    screen.render_text(
-        mid_x,
-        mid_y + 1,
-        "To exit press q!".to_string(),
-        Quad::Center,
-    )?;
-
     input.join().unwrap();
 
     Ok(())
@@ -123,31 +136,44 @@ fn main() {
 
     let mut w = 20;
     let mut h = 20;
+    let mut aspect = Aspect::Spaced;
 
     let mut iter = args.iter();
     while let Some(arg) = iter.next() {
         match arg.as_str() {
             "-w" | "--width" => {
                 if let Some(val) = iter.next() {
-                    w = val.parse().unwrap();
-                    if !(0..=100).contains(&w) {
-                        eprintln!("Width is not in range [0, 100]!");
+                    w = val.parse().unwrap_or(0);
+                    if !(1..=100).contains(&w) {
+                        eprintln!("Width is not in range [1, 100]!");
                         return;
                     }
                 }
             }
             "-h" | "--height" => {
                 if let Some(val) = iter.next() {
-                    h = val.parse().unwrap();
-                    if !(0..=100).contains(&h) {
-                        eprintln!("Height is not in range [0, 100]!");
+                    h = val.parse().unwrap_or(0);
+                    if !(1..=100).contains(&h) {
+                        eprintln!("Height is not in range [1, 100]!");
                         return;
                     }
                 }
             }
+            "-a" | "--aspect" => match iter.next().map(String::as_str) {
+                Some("spaced" | "s") => aspect = Aspect::Spaced,
+                Some("doubled" | "d") => aspect = Aspect::Doubled,
+                Some("single" | "1") => aspect = Aspect::Single,
+                _ => {
+                    eprintln!("Invalid aspect, not in {{s, d, 1, spaced, doubled, single}}");
+                    return;
+                }
+            },
             _ => {}
         }
     }
 
-    play_snake(w, h).unwrap();
+    if let Err(e) = play_snake(w, h, aspect) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
 }
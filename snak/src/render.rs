@@ -1,8 +1,12 @@
 use std::iter;
 
-use renderer::traits::Renderble;
+use rand::Rng;
+use renderer::{
+    traits::{Renderble, SizedRenderble},
+    types::Size,
+};
 
-use crate::snake::{Status, Tile};
+use crate::snake::{Snake, Status, Tile};
 
 impl Renderble for Tile {
     type Primitive = char;
@@ -25,3 +29,20 @@ impl Renderble for Status {
         owned.into_iter()
     }
 }
+
+impl<R> SizedRenderble for Snake<R>
+where
+    R: Rng,
+{
+    type Primitive = char;
+
+    fn size(&self) -> Size {
+        self.bbox()
+    }
+
+    fn render_rows(&self) -> impl Iterator<Item = impl Iterator<Item = Self::Primitive>> {
+        self.tiles()
+            .chunks(self.bbox().w)
+            .map(|row| row.iter().flat_map(|x| x.render()))
+    }
+}
@@ -2,6 +2,7 @@ use rand::Rng;
 use renderer::{
     traits::{RenderTarget, Renderble},
     types::Size,
+    Error,
 };
 
 use std::collections::LinkedList;
@@ -75,6 +76,14 @@ where
         }
     }
 
+    pub fn bbox(&self) -> Size {
+        self.bbox
+    }
+
+    pub fn tiles(&self) -> &[Tile] {
+        &self.buf
+    }
+
     pub fn change_dir(&mut self, new: Dir) {
         if new.is_vertical() ^ self.dir.is_vertical() {
             self.dir = new;
@@ -91,14 +100,20 @@ where
         self.buf[idx] = Tile::Empty
     }
 
-    fn next_food(&mut self) {
-        loop {
-            let food = self.rng.gen_range(0..self.bbox.flatten() - self.bbox.w);
-            if self.buf[food] == Tile::Empty {
-                self.buf[food] = Tile::Food;
-                break;
-            }
+    /// puts food on an empty tile off the bottom row, a board of one row has only
+    /// that one, false when the snake fills every tile it could go on
+    fn next_food(&mut self) -> bool {
+        let end = match self.bbox.h {
+            1 => self.bbox.flatten(),
+            _ => self.bbox.flatten() - self.bbox.w,
+        };
+        let empty: Vec<usize> = (0..end).filter(|&i| self.buf[i] == Tile::Empty).collect();
+        if empty.is_empty() {
+            return false;
         }
+        let food = empty[self.rng.gen_range(0..empty.len())];
+        self.buf[food] = Tile::Food;
+        true
     }
 
     pub fn start(&mut self) {
@@ -113,7 +128,7 @@ where
 
     fn change_score(&mut self) {
         self.status.score += 1;
-        self.status.diff += (self.status.score % 10 == 0) as usize
+        self.status.diff += self.status.score.is_multiple_of(10) as usize
     }
 
     /// returns whether the snake died or not
@@ -157,8 +172,11 @@ where
             match self.buf[idx] {
                 Tile::Food => {
                     self.advance(idx);
-                    self.next_food();
                     self.change_score();
+                    // nothing is left to eat once the snake fills the board
+                    if !self.next_food() {
+                        return Err(());
+                    }
                     Ok(self.status.diff)
                 }
                 Tile::Empty => {
@@ -179,9 +197,10 @@ where
 
     pub fn draw_snake_to<F>(&self, target: &mut F) -> Result<(), F::Error>
     where
-        F: RenderTarget<<Tile as Renderble>::Primitive>,
+        F: RenderTarget<char>,
+        F::Error: From<Error>,
     {
-        target.draw(self.buf.iter().flat_map(|x| x.render()))
+        target.draw_sized(self)
     }
 
     pub fn draw_status_to<F>(&self, target: &mut F) -> Result<(), F::Error>
@@ -191,3 +210,30 @@ where
         target.draw(self.status.render())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+    use renderer::types::Size;
+
+    use super::{Dir, Snake, Tile};
+
+    #[test]
+    fn one_row_board() {
+        let mut snake = Snake::new(Size::new(2, 1), StdRng::seed_from_u64(1));
+        snake.start();
+        assert_eq!(snake.tiles(), [Tile::Food, Tile::Snake]);
+
+        snake.change_dir(Dir::Left);
+        assert!(snake.tick_move().is_err());
+        assert_eq!(snake.tiles(), [Tile::Snake, Tile::Snake]);
+    }
+
+    #[test]
+    fn single_tile_board() {
+        let mut snake = Snake::new(Size::new(1, 1), StdRng::seed_from_u64(1));
+        snake.start();
+        assert_eq!(snake.tiles(), [Tile::Snake]);
+        assert!(snake.tick_move().is_err());
+    }
+}